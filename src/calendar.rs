@@ -0,0 +1,339 @@
+//! Calendar hierarchy for date nodes
+//!
+//! Date nodes use deterministic IDs derived from the period they represent
+//! (`date:2025`, `date:2025-06`, `date:2025-W27`, `date:2025-06-30`), so every
+//! device and service resolves "navigate to a date" to the same nodes. The
+//! generator builds the missing part of the year → month → day chain (or the ISO
+//! year → week → day chain) and links each new node in chronological order
+//! among its siblings. Zero-padded IDs sort chronologically, which the
+//! generator relies on to find the sibling position.
+
+use crate::hierarchy::{HierarchyChangeSet, Staging};
+use crate::locale::{DateLocale, DEFAULT_LOCALE};
+use crate::{DateContent, DateNodeMetadata, Node, NodeId, NodeSpaceResult, NodeType};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Prefix shared by all calendar node IDs
+pub const DATE_ID_PREFIX: &str = "date:";
+
+/// Span of time a date node represents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarPeriod {
+    Year,
+    Month,
+    Week,
+    #[default]
+    Day,
+}
+
+impl CalendarPeriod {
+    pub fn is_day(&self) -> bool {
+        matches!(self, CalendarPeriod::Day)
+    }
+}
+
+/// Shape of the calendar hierarchy above day nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarLayout {
+    /// year → month → day
+    #[default]
+    Monthly,
+    /// ISO week-year → ISO week → day
+    ///
+    /// Days at the turn of the year belong to the ISO week-year, so
+    /// 2024-12-30 sits under `date:2025-W01` and `date:2025`.
+    IsoWeekly,
+}
+
+impl NodeId {
+    /// Deterministic ID of the day node for a date
+    pub fn for_date(date: NaiveDate) -> Self {
+        Self(format!("{}{}", DATE_ID_PREFIX, date.format("%Y-%m-%d")))
+    }
+
+    /// Deterministic ID of a month node
+    pub fn for_month(year: i32, month: u32) -> Self {
+        Self(format!("{}{:04}-{:02}", DATE_ID_PREFIX, year, month))
+    }
+
+    /// Deterministic ID of an ISO week node
+    pub fn for_iso_week(week: chrono::IsoWeek) -> Self {
+        Self(format!(
+            "{}{:04}-W{:02}",
+            DATE_ID_PREFIX,
+            week.year(),
+            week.week()
+        ))
+    }
+
+    /// Deterministic ID of a year node
+    pub fn for_year(year: i32) -> Self {
+        Self(format!("{}{:04}", DATE_ID_PREFIX, year))
+    }
+
+    /// Parse the date back out of a day node ID
+    pub fn to_date(&self) -> Option<NaiveDate> {
+        let day = self.0.strip_prefix(DATE_ID_PREFIX)?;
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()
+    }
+}
+
+impl Node {
+    /// Create a calendar node for a period starting at `start`
+    ///
+    /// The ID is deterministic and `date_metadata.date` holds the first day of the
+    /// period. Parent and sibling pointers are left unset; use
+    /// [`ensure_date_path`] to place the node in a hierarchy.
+    pub fn new_calendar_node(start: NaiveDate, period: CalendarPeriod) -> Self {
+        let id = match period {
+            CalendarPeriod::Year => NodeId::for_year(start.year()),
+            CalendarPeriod::Month => NodeId::for_month(start.year(), start.month()),
+            CalendarPeriod::Week => NodeId::for_iso_week(start.iso_week()),
+            CalendarPeriod::Day => NodeId::for_date(start),
+        };
+        let label = DateLocale::resolve(DEFAULT_LOCALE).format_period(start, period);
+
+        let date_metadata = DateNodeMetadata {
+            display_format: label.clone(),
+            ..DateNodeMetadata::new(start)
+        };
+        let content = DateContent {
+            content: label,
+            date_metadata,
+            period,
+        };
+        let content =
+            serde_json::to_value(content).expect("date content serializes to a JSON object");
+        Self::with_id_of_kind(id, NodeType::Date, content)
+    }
+
+    /// Period a date node represents, `None` for other nodes
+    pub fn calendar_period(&self) -> Option<CalendarPeriod> {
+        if !self.is_date_node() {
+            return None;
+        }
+        Some(
+            self.content
+                .get("period")
+                .and_then(|p| serde_json::from_value(p.clone()).ok())
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// The chain of calendar nodes from the year down to the day, as (start, period)
+fn calendar_path(date: NaiveDate, layout: CalendarLayout) -> [(NaiveDate, CalendarPeriod); 3] {
+    match layout {
+        CalendarLayout::Monthly => [
+            (
+                NaiveDate::from_ymd_opt(date.year(), 1, 1).expect("January 1st exists"),
+                CalendarPeriod::Year,
+            ),
+            (
+                date.with_day(1).expect("every month has a first day"),
+                CalendarPeriod::Month,
+            ),
+            (date, CalendarPeriod::Day),
+        ],
+        CalendarLayout::IsoWeekly => {
+            let week = date.iso_week();
+            let week_start =
+                NaiveDate::from_isoywd_opt(week.year(), week.week(), chrono::Weekday::Mon)
+                    .expect("ISO week of an existing date has a Monday");
+            [
+                (
+                    NaiveDate::from_ymd_opt(week.year(), 1, 1).expect("January 1st exists"),
+                    CalendarPeriod::Year,
+                ),
+                (week_start, CalendarPeriod::Week),
+                (date, CalendarPeriod::Day),
+            ]
+        }
+    }
+}
+
+/// Build or look up the calendar chain for a date
+///
+/// Returns only the nodes that had to be created, restored from a tombstone or
+/// linked into the chain, so calling it again for the same date yields an empty
+/// change set. Existing calendar nodes are reused; a standalone one without a
+/// parent, such as a day from [`Node::new_date_node`], is moved under its
+/// calendar parent at its sorted position. The day node ID is
+/// [`NodeId::for_date`].
+pub fn ensure_date_path(
+    nodes: &[Node],
+    date: NaiveDate,
+    layout: CalendarLayout,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    ensure_path(&mut staging, date, layout)?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Build or look up the calendar chain for every day from `start` to `end`
+/// inclusive
+pub fn ensure_date_range(
+    nodes: &[Node],
+    start: NaiveDate,
+    end: NaiveDate,
+    layout: CalendarLayout,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    for date in start.iter_days().take_while(|d| *d <= end) {
+        ensure_path(&mut staging, date, layout)?;
+    }
+    Ok(staging.finish(Vec::new()))
+}
+
+pub(crate) fn ensure_path(
+    staging: &mut Staging,
+    date: NaiveDate,
+    layout: CalendarLayout,
+) -> NodeSpaceResult<()> {
+    let mut parent: Option<NodeId> = None;
+    for (start, period) in calendar_path(date, layout) {
+        let node = Node::new_calendar_node(start, period);
+        let id = node.id.clone();
+
+        match staging
+            .get(&id)
+            .map(|n| (n.is_deleted(), n.parent_id.is_none()))
+        {
+            Ok((deleted, top_level)) => {
+                if deleted {
+                    staging.restore(&id)?;
+                }
+                // Standalone date nodes, e.g. from `Node::new_date_node`, join the chain
+                match parent.as_ref() {
+                    Some(parent) if top_level => {
+                        let previous = previous_date_sibling(staging, parent, &id);
+                        staging.move_node(&id, Some(parent), previous.as_ref())?;
+                    }
+                    None if staging.get(&id)?.root_id.as_ref() != Some(&id) => {
+                        staging.propagate_root(&id)?;
+                    }
+                    _ => {}
+                }
+            }
+            Err(_) => match parent.as_ref() {
+                None => {
+                    let id = staging.insert(node)?;
+                    staging.edit(&id)?.root_id = Some(id.clone());
+                }
+                Some(parent) => {
+                    match previous_date_sibling(staging, parent, &id) {
+                        Some(previous) => staging.insert_after(node, &previous)?,
+                        None => staging.insert_as_first_child(node, parent)?,
+                    };
+                }
+            },
+        }
+        parent = Some(id);
+    }
+    Ok(())
+}
+
+/// Latest live calendar sibling under `parent` that sorts before `id`
+fn previous_date_sibling(staging: &Staging, parent: &NodeId, id: &NodeId) -> Option<NodeId> {
+    staging
+        .children_of(Some(parent))
+        .into_iter()
+        .filter(|sibling| {
+            sibling.as_str().starts_with(DATE_ID_PREFIX) && sibling.as_str() < id.as_str()
+        })
+        .max_by(|a, b| a.as_str().cmp(b.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::validate_hierarchy;
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn ensure(nodes: &mut Vec<Node>, date: NaiveDate) -> HierarchyChangeSet {
+        let changes = ensure_date_path(nodes, date, CalendarLayout::Monthly).unwrap();
+        changes.apply_to(nodes);
+        changes
+    }
+
+    fn get<'n>(nodes: &'n [Node], id: &NodeId) -> &'n Node {
+        nodes.iter().find(|n| &n.id == id).unwrap()
+    }
+
+    #[test]
+    fn builds_the_chain_once() {
+        let mut nodes = Vec::new();
+        let created = ensure(&mut nodes, day(2025, 6, 30));
+        assert_eq!(created.updated.len(), 3);
+
+        let today = get(&nodes, &NodeId::for_date(day(2025, 6, 30)));
+        assert_eq!(today.parent_id, Some(NodeId::for_month(2025, 6)));
+        assert_eq!(today.root_id, Some(NodeId::for_year(2025)));
+        assert!(validate_hierarchy(&nodes).is_empty());
+
+        assert!(ensure(&mut nodes, day(2025, 6, 30)).is_empty());
+    }
+
+    #[test]
+    fn attaches_a_standalone_day_node() {
+        let date = day(2025, 6, 30);
+        let standalone = Node::new_date_node(date);
+        let mut child = Node::new("text".to_string(), "Note".into());
+        child.parent_id = Some(standalone.id.clone());
+        child.root_id = Some(standalone.id.clone());
+        let mut nodes = vec![standalone.clone(), child.clone()];
+
+        ensure(&mut nodes, date);
+
+        let attached = get(&nodes, &standalone.id);
+        assert_eq!(attached.parent_id, Some(NodeId::for_month(2025, 6)));
+        assert_eq!(attached.root_id, Some(NodeId::for_year(2025)));
+        assert_eq!(get(&nodes, &child.id).root_id, Some(NodeId::for_year(2025)));
+        assert_eq!(nodes.len(), 4);
+        assert!(validate_hierarchy(&nodes).is_empty());
+        assert!(ensure(&mut nodes, date).is_empty());
+    }
+
+    #[test]
+    fn attaches_a_standalone_day_at_its_sorted_position() {
+        let mut nodes = Vec::new();
+        ensure(&mut nodes, day(2025, 6, 1));
+        ensure(&mut nodes, day(2025, 6, 20));
+        nodes.push(Node::new_date_node(day(2025, 6, 10)));
+
+        ensure(&mut nodes, day(2025, 6, 10));
+
+        let middle = get(&nodes, &NodeId::for_date(day(2025, 6, 10)));
+        assert_eq!(
+            middle.before_sibling,
+            Some(NodeId::for_date(day(2025, 6, 1)))
+        );
+        assert_eq!(
+            middle.next_sibling,
+            Some(NodeId::for_date(day(2025, 6, 20)))
+        );
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+
+    #[test]
+    fn restores_a_tombstoned_day() {
+        let date = day(2025, 6, 30);
+        let mut nodes = Vec::new();
+        ensure(&mut nodes, date);
+        let id = NodeId::for_date(date);
+        crate::hierarchy::tombstone_subtree(&nodes, &id, "test")
+            .unwrap()
+            .apply_to(&mut nodes);
+
+        let restored = ensure(&mut nodes, date);
+        assert_eq!(restored.updated.len(), 1);
+        assert!(!get(&nodes, &id).is_deleted());
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+//! Strongly-typed node content
+//!
+//! A [`NodeContent`] type knows which `Node.r#type` it belongs to and how its
+//! `content` JSON is shaped. [`Node::from_typed`] and [`Node::content_as`] convert
+//! between the generic node and these types, checking `r#type` in both directions
+//! so typed content can't be read as the wrong kind.
+
+use crate::calendar::CalendarPeriod;
+use crate::{DateNodeMetadata, Node, NodeSpaceResult, ProcessingError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Typed content for a single node type
+pub trait NodeContent: Serialize + DeserializeOwned {
+    /// Value of `Node.r#type` for nodes holding this content
+    const NODE_TYPE: &'static str;
+}
+
+/// Plain text content, stored as a bare JSON string
+///
+/// Also reads the `{"content": "..."}` object that older text nodes were created
+/// with; it is written back as a bare string.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct TextContent {
+    pub text: String,
+}
+
+impl<'de> Deserialize<'de> for TextContent {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Shape {
+            Bare(String),
+            Object { content: String },
+        }
+
+        let text = match Shape::deserialize(deserializer)? {
+            Shape::Bare(text) | Shape::Object { content: text } => text,
+        };
+        Ok(Self { text })
+    }
+}
+
+impl TextContent {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+        }
+    }
+}
+
+impl NodeContent for TextContent {
+    const NODE_TYPE: &'static str = "text";
+}
+
+/// Date content, matching the structure produced by [`Node::new_date_node`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "date")]
+pub struct DateContent {
+    /// Display text for the date
+    pub content: String,
+    pub date_metadata: DateNodeMetadata,
+    /// Span the node represents; day nodes omit the field
+    #[serde(default, skip_serializing_if = "CalendarPeriod::is_day")]
+    pub period: CalendarPeriod,
+}
+
+impl DateContent {
+    pub fn new(date: chrono::NaiveDate) -> Self {
+        let date_metadata = DateNodeMetadata::new(date);
+        Self {
+            content: date_metadata.display_format.clone(),
+            date_metadata,
+            period: CalendarPeriod::Day,
+        }
+    }
+}
+
+impl NodeContent for DateContent {
+    const NODE_TYPE: &'static str = "date";
+}
+
+/// Link content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkContent {
+    pub url: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl LinkContent {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            title: None,
+            description: None,
+        }
+    }
+}
+
+impl NodeContent for LinkContent {
+    const NODE_TYPE: &'static str = "link";
+}
+
+impl Node {
+    /// Create a node from typed content, setting `r#type` from the content type
+    ///
+    /// ```
+    /// use nodespace_core_types::{Node, TaskContent, TextContent};
+    ///
+    /// let node = Node::from_typed(TaskContent::new("Write release notes")).unwrap();
+    /// assert_eq!(node.r#type, "task");
+    /// assert_eq!(node.content_as::<TaskContent>().unwrap().title, "Write release notes");
+    /// assert!(node.content_as::<TextContent>().is_err());
+    /// ```
+    pub fn from_typed<T: NodeContent>(content: T) -> NodeSpaceResult<Self> {
+        let content_value =
+            serde_json::to_value(content).map_err(|e| serialization_failed::<T>(e.to_string()))?;
+        Ok(Self::new(T::NODE_TYPE.to_string(), content_value))
+    }
+
+    /// Read the content as a typed value
+    ///
+    /// Fails with [`ProcessingError::SerializationFailed`] when `r#type` does not
+    /// match `T` or the content does not have the expected shape.
+    pub fn content_as<T: NodeContent>(&self) -> NodeSpaceResult<T> {
+        if self.r#type != T::NODE_TYPE {
+            return Err(serialization_failed::<T>(format!(
+                "node type '{}' does not match '{}'",
+                self.r#type,
+                T::NODE_TYPE
+            ))
+            .into());
+        }
+        serde_json::from_value(self.content.clone())
+            .map_err(|e| serialization_failed::<T>(e.to_string()).into())
+    }
+
+    /// Check if the node's type matches the typed content `T`
+    pub fn holds<T: NodeContent>(&self) -> bool {
+        self.r#type == T::NODE_TYPE
+    }
+}
+
+fn serialization_failed<T>(reason: String) -> ProcessingError {
+    ProcessingError::SerializationFailed {
+        format: "JSON".to_string(),
+        reason,
+        data_type: std::any::type_name::<T>().to_string(),
+        fallback_formats: vec!["MessagePack".to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    fn round_trip<T: NodeContent + PartialEq + std::fmt::Debug>(content: T) -> Node {
+        let node = Node::from_typed(content).unwrap();
+        let text = serde_json::to_string(&node).unwrap();
+        let parsed: Node = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed.r#type, T::NODE_TYPE);
+        assert_eq!(parsed.content, node.content);
+        parsed
+    }
+
+    #[test]
+    fn text_content_round_trip() {
+        let text = TextContent::new("Hello world");
+        let node = round_trip(text.clone());
+        assert_eq!(node.content, json!("Hello world"));
+        assert_eq!(node.content_as::<TextContent>().unwrap(), text);
+    }
+
+    #[test]
+    fn text_content_reads_object_shape() {
+        let node = Node::new("text".to_string(), json!({"content": "Hello world"}));
+        let text = node.content_as::<TextContent>().unwrap();
+        assert_eq!(text, TextContent::new("Hello world"));
+        assert_eq!(serde_json::to_value(&text).unwrap(), json!("Hello world"));
+
+        let node = Node::new("text".to_string(), json!({"body": "Hello"}));
+        assert!(node.content_as::<TextContent>().is_err());
+    }
+
+    #[test]
+    fn date_content_round_trip() {
+        let date = DateContent::new(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap());
+        let node = round_trip(date.clone());
+        assert_eq!(node.content_as::<DateContent>().unwrap(), date);
+        assert_eq!(node.content["type"], json!("date"));
+        assert!(node.content.get("period").is_none());
+
+        let date_node = Node::new_date_node(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap());
+        assert_eq!(date_node.content_as::<DateContent>().unwrap(), date);
+    }
+
+    #[test]
+    fn link_content_round_trip() {
+        let mut link = LinkContent::new("https://example.com");
+        let node = round_trip(link.clone());
+        assert_eq!(
+            node.content,
+            json!({"url": "https://example.com", "title": null, "description": null})
+        );
+        assert_eq!(node.content_as::<LinkContent>().unwrap(), link);
+
+        link.title = Some("Example".to_string());
+        link.description = Some("An example site".to_string());
+        let node = round_trip(link.clone());
+        assert_eq!(node.content_as::<LinkContent>().unwrap(), link);
+
+        let sparse = Node::new("link".to_string(), json!({"url": "https://example.com"}));
+        assert_eq!(
+            sparse.content_as::<LinkContent>().unwrap(),
+            LinkContent::new("https://example.com")
+        );
+    }
+
+    #[test]
+    fn content_as_checks_node_type() {
+        let node = Node::from_typed(LinkContent::new("https://example.com")).unwrap();
+        assert!(node.holds::<LinkContent>());
+        assert!(!node.holds::<TextContent>());
+        assert!(node.content_as::<TextContent>().is_err());
+    }
+}
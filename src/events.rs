@@ -0,0 +1,645 @@
+//! Node change-log events with deterministic replay
+//!
+//! [`NodeEvent`] is the shared vocabulary for data-store change logs, the sync
+//! layer and undo stacks. Replaying the same event stream onto the same state
+//! always produces the same nodes: structural events go through the
+//! [`hierarchy`](crate::hierarchy) operations, and every changed node takes its
+//! `updated_at` from the event rather than from the wall clock.
+
+use crate::hierarchy::{HierarchyChangeSet, Staging};
+use crate::{DatabaseError, Node, NodeId, NodeSpaceError, NodeSpaceResult, ValidationError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A single change to a node, attributed to an actor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum NodeEvent {
+    /// Node created at the position recorded in its `before_sibling` / `parent_id`
+    Created {
+        node: Box<Node>,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// Node content replaced
+    ContentUpdated {
+        node_id: NodeId,
+        content: serde_json::Value,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// Node metadata replaced (`None` clears it)
+    MetadataUpdated {
+        node_id: NodeId,
+        metadata: Option<serde_json::Value>,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// Subtree moved under a new parent, after `after` (first child when `None`)
+    Moved {
+        node_id: NodeId,
+        new_parent: Option<NodeId>,
+        after: Option<NodeId>,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// Node moved within its current parent, after `after` (first when `None`)
+    Reordered {
+        node_id: NodeId,
+        after: Option<NodeId>,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// Node and its descendants soft-deleted (tombstoned by the event's actor)
+    Deleted {
+        node_id: NodeId,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// Previously deleted subtree brought back
+    ///
+    /// When the state still holds the tombstoned subtree it is restored in place.
+    /// Otherwise (for example after tombstones were purged) `nodes` must hold the
+    /// subtree as it was before deletion, including `node_id` itself; the subtree
+    /// root is relinked at its recorded sibling position.
+    Restored {
+        node_id: NodeId,
+        #[serde(default)]
+        nodes: Vec<Node>,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+impl NodeEvent {
+    /// Create a `Created` event stamped with the current time
+    pub fn created(node: Node, actor: &str) -> Self {
+        Self::Created {
+            node: Box::new(node),
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Create a `ContentUpdated` event stamped with the current time
+    pub fn content_updated(node_id: NodeId, content: serde_json::Value, actor: &str) -> Self {
+        Self::ContentUpdated {
+            node_id,
+            content,
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Create a `MetadataUpdated` event stamped with the current time
+    pub fn metadata_updated(
+        node_id: NodeId,
+        metadata: Option<serde_json::Value>,
+        actor: &str,
+    ) -> Self {
+        Self::MetadataUpdated {
+            node_id,
+            metadata,
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Create a `Moved` event stamped with the current time
+    pub fn moved(
+        node_id: NodeId,
+        new_parent: Option<NodeId>,
+        after: Option<NodeId>,
+        actor: &str,
+    ) -> Self {
+        Self::Moved {
+            node_id,
+            new_parent,
+            after,
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Create a `Reordered` event stamped with the current time
+    pub fn reordered(node_id: NodeId, after: Option<NodeId>, actor: &str) -> Self {
+        Self::Reordered {
+            node_id,
+            after,
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Create a `Deleted` event stamped with the current time
+    pub fn deleted(node_id: NodeId, actor: &str) -> Self {
+        Self::Deleted {
+            node_id,
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Create a `Restored` event stamped with the current time
+    pub fn restored(node_id: NodeId, nodes: Vec<Node>, actor: &str) -> Self {
+        Self::Restored {
+            node_id,
+            nodes,
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// ID of the node the event applies to
+    pub fn node_id(&self) -> &NodeId {
+        match self {
+            Self::Created { node, .. } => &node.id,
+            Self::ContentUpdated { node_id, .. }
+            | Self::MetadataUpdated { node_id, .. }
+            | Self::Moved { node_id, .. }
+            | Self::Reordered { node_id, .. }
+            | Self::Deleted { node_id, .. }
+            | Self::Restored { node_id, .. } => node_id,
+        }
+    }
+
+    /// Actor (user, device or service) responsible for the event
+    pub fn actor(&self) -> &str {
+        match self {
+            Self::Created { actor, .. }
+            | Self::ContentUpdated { actor, .. }
+            | Self::MetadataUpdated { actor, .. }
+            | Self::Moved { actor, .. }
+            | Self::Reordered { actor, .. }
+            | Self::Deleted { actor, .. }
+            | Self::Restored { actor, .. } => actor,
+        }
+    }
+
+    /// When the event happened
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            Self::Created { timestamp, .. }
+            | Self::ContentUpdated { timestamp, .. }
+            | Self::MetadataUpdated { timestamp, .. }
+            | Self::Moved { timestamp, .. }
+            | Self::Reordered { timestamp, .. }
+            | Self::Deleted { timestamp, .. }
+            | Self::Restored { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Short name of the event kind, matching its serialized tag
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Created { .. } => "created",
+            Self::ContentUpdated { .. } => "content_updated",
+            Self::MetadataUpdated { .. } => "metadata_updated",
+            Self::Moved { .. } => "moved",
+            Self::Reordered { .. } => "reordered",
+            Self::Deleted { .. } => "deleted",
+            Self::Restored { .. } => "restored",
+        }
+    }
+}
+
+/// Apply a single event to a node map
+///
+/// Events that cannot be applied (unknown node, duplicate creation, invalid move)
+/// are rejected with [`ValidationError::BusinessRuleViolation`] and leave the state
+/// untouched.
+pub fn apply_event(state: &mut HashMap<NodeId, Node>, event: &NodeEvent) -> NodeSpaceResult<()> {
+    let timestamp = event.timestamp();
+    let change_set = stage_event(state, event).map_err(|reason| rejected(event, reason))?;
+
+    for id in &change_set.removed {
+        state.remove(id);
+    }
+    for mut node in change_set.updated {
+        node.updated_at = timestamp;
+        state.insert(node.id.clone(), node);
+    }
+    Ok(())
+}
+
+/// Replay a stream of events onto a node map, in order
+///
+/// Stops at the first rejected event; the state then reflects every event before
+/// it.
+pub fn replay<'a>(
+    state: &mut HashMap<NodeId, Node>,
+    events: impl IntoIterator<Item = &'a NodeEvent>,
+) -> NodeSpaceResult<()> {
+    for event in events {
+        apply_event(state, event)?;
+    }
+    Ok(())
+}
+
+/// Compute the nodes an event changes without touching the state
+fn stage_event(
+    state: &HashMap<NodeId, Node>,
+    event: &NodeEvent,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    // Stage in id order: HashMap iteration order differs between maps, and the
+    // change set must not depend on it
+    let mut staged: Vec<&Node> = state.values().collect();
+    staged.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    let mut staging = Staging::new(staged);
+
+    match event {
+        NodeEvent::Created { node, .. } => {
+            ensure_absent(state, &node.id)?;
+            insert_at_recorded_position(&mut staging, node.as_ref().clone())?;
+        }
+        NodeEvent::ContentUpdated {
+            node_id, content, ..
+        } => {
+            let mut node = existing(state, node_id)?.clone();
+            node.content = content.clone();
+            return Ok(single(node));
+        }
+        NodeEvent::MetadataUpdated {
+            node_id, metadata, ..
+        } => {
+            let mut node = existing(state, node_id)?.clone();
+            node.metadata = metadata.clone();
+            return Ok(single(node));
+        }
+        NodeEvent::Moved {
+            node_id,
+            new_parent,
+            after,
+            ..
+        } => {
+            staging.move_node(node_id, new_parent.as_ref(), after.as_ref())?;
+        }
+        NodeEvent::Reordered { node_id, after, .. } => {
+            let parent = existing(state, node_id)?.parent_id.clone();
+            staging.move_node(node_id, parent.as_ref(), after.as_ref())?;
+        }
+        NodeEvent::Deleted {
+            node_id,
+            actor,
+            timestamp,
+        } => {
+            staging.tombstone(node_id, actor, *timestamp)?;
+        }
+        NodeEvent::Restored { node_id, .. } if state.contains_key(node_id) => {
+            staging.restore(node_id)?;
+        }
+        NodeEvent::Restored { node_id, nodes, .. } => {
+            for node in nodes {
+                ensure_absent(state, &node.id)?;
+            }
+            let root = nodes.iter().find(|n| &n.id == node_id).ok_or_else(|| {
+                ValidationError::required_field("nodes[node_id]", "Restored event")
+            })?;
+            insert_at_recorded_position(&mut staging, root.clone())?;
+
+            let mut change_set = staging.finish(Vec::new());
+            change_set
+                .updated
+                .extend(nodes.iter().filter(|n| &n.id != node_id).cloned());
+            return Ok(change_set);
+        }
+    }
+
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Link a new node after its recorded previous sibling, or as the first child of
+/// its recorded parent, or as a standalone root
+fn insert_at_recorded_position(staging: &mut Staging, node: Node) -> NodeSpaceResult<()> {
+    match (node.before_sibling.clone(), node.parent_id.clone()) {
+        (Some(before), parent) => {
+            let anchor_parent = staging.get(&before)?.parent_id.clone();
+            if parent.is_some() && parent != anchor_parent {
+                return Err(ValidationError::invalid_format(
+                    "before_sibling",
+                    "a sibling under the recorded parent",
+                    before.as_str(),
+                )
+                .into());
+            }
+            staging.insert_after(node, &before)?;
+        }
+        (None, Some(parent)) => {
+            staging.insert_as_first_child(node, &parent)?;
+        }
+        (None, None) => {
+            staging.insert(node)?;
+        }
+    }
+    Ok(())
+}
+
+/// Look up a live node; tombstones count as missing
+fn existing<'a>(state: &'a HashMap<NodeId, Node>, id: &NodeId) -> NodeSpaceResult<&'a Node> {
+    state
+        .get(id)
+        .filter(|n| !n.is_deleted())
+        .ok_or_else(|| DatabaseError::not_found("Node", id.as_str()).into())
+}
+
+fn ensure_absent(state: &HashMap<NodeId, Node>, id: &NodeId) -> NodeSpaceResult<()> {
+    if state.contains_key(id) {
+        return Err(DatabaseError::ConstraintViolation {
+            constraint: "unique node id".to_string(),
+            table: "nodes".to_string(),
+            conflicting_value: id.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+fn single(mut node: Node) -> HierarchyChangeSet {
+    node.revision += 1;
+    HierarchyChangeSet {
+        updated: vec![node],
+        removed: Vec::new(),
+    }
+}
+
+fn rejected(event: &NodeEvent, reason: NodeSpaceError) -> NodeSpaceError {
+    ValidationError::BusinessRuleViolation {
+        rule: format!(
+            "{} event for node {} cannot be applied",
+            event.kind(),
+            event.node_id()
+        ),
+        context: serde_json::json!({
+            "event": serde_json::to_value(event).unwrap_or(serde_json::Value::Null),
+            "reason": reason.to_string(),
+        }),
+        resolution_steps: vec![
+            "Replay events in their original order".to_string(),
+            "Check that the target node exists and has not been deleted".to_string(),
+        ],
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::validate_hierarchy;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+
+    fn id(id: &str) -> NodeId {
+        NodeId::from_string(id.to_string())
+    }
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 1, 9, minute, 0).unwrap()
+    }
+
+    fn node(name: &str, parent: Option<&str>, before: Option<&str>) -> Node {
+        let mut node = Node::with_id(id(name), "text".to_string(), serde_json::json!(name));
+        node.created_at = at(0);
+        node.updated_at = at(0);
+        node.parent_id = parent.map(id);
+        node.before_sibling = before.map(id);
+        node
+    }
+
+    fn created(node: Node, minute: u32) -> NodeEvent {
+        NodeEvent::Created {
+            node: Box::new(node),
+            actor: "alice".to_string(),
+            timestamp: at(minute),
+        }
+    }
+
+    /// root ─┬─ a
+    ///       ├─ b
+    ///       └─ c
+    fn creation_log() -> Vec<NodeEvent> {
+        let mut root = node("root", None, None);
+        root.mark_as_hierarchy_root();
+        vec![
+            created(root, 1),
+            created(node("a", Some("root"), None), 2),
+            created(node("b", Some("root"), Some("a")), 3),
+            created(node("c", Some("root"), Some("b")), 4),
+        ]
+    }
+
+    fn replayed(events: &[NodeEvent]) -> HashMap<NodeId, Node> {
+        let mut state = HashMap::new();
+        replay(&mut state, events).unwrap();
+        state
+    }
+
+    /// Children of `parent` by walking the sibling chain from its first child
+    fn chain(state: &HashMap<NodeId, Node>, parent: &str) -> Vec<String> {
+        let mut current = state.values().find(|n| {
+            n.parent_id == Some(id(parent)) && n.before_sibling.is_none() && !n.is_deleted()
+        });
+        let mut result = Vec::new();
+        while let Some(node) = current {
+            result.push(node.id.to_string());
+            current = node.next_sibling.as_ref().and_then(|next| state.get(next));
+        }
+        result
+    }
+
+    fn snapshot(state: &HashMap<NodeId, Node>) -> serde_json::Value {
+        let sorted: BTreeMap<&str, &Node> = state.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        serde_json::to_value(sorted).unwrap()
+    }
+
+    fn is_valid(state: &HashMap<NodeId, Node>) -> bool {
+        let nodes: Vec<Node> = state.values().cloned().collect();
+        validate_hierarchy(&nodes).is_empty()
+    }
+
+    #[test]
+    fn replaying_the_same_log_gives_identical_nodes() {
+        let mut events = creation_log();
+        events.extend([
+            NodeEvent::Moved {
+                node_id: id("c"),
+                new_parent: Some(id("a")),
+                after: None,
+                actor: "bob".to_string(),
+                timestamp: at(5),
+            },
+            NodeEvent::Reordered {
+                node_id: id("a"),
+                after: Some(id("b")),
+                actor: "bob".to_string(),
+                timestamp: at(6),
+            },
+            NodeEvent::Deleted {
+                node_id: id("a"),
+                actor: "bob".to_string(),
+                timestamp: at(7),
+            },
+        ]);
+
+        // Every map gets its own hash seed, so iteration order differs per replay
+        let expected = snapshot(&replayed(&events));
+        for _ in 0..16 {
+            assert_eq!(snapshot(&replayed(&events)), expected);
+        }
+    }
+
+    #[test]
+    fn created_links_at_recorded_position() {
+        let state = replayed(&creation_log());
+        assert_eq!(chain(&state, "root"), vec!["a", "b", "c"]);
+        assert_eq!(state[&id("c")].updated_at, at(4));
+        // b was relinked to c by the last event
+        assert_eq!(state[&id("b")].updated_at, at(4));
+        assert_eq!(state[&id("b")].root_id, Some(id("root")));
+        assert!(is_valid(&state));
+
+        let mut state = state;
+        let duplicate = created(node("a", Some("root"), None), 5);
+        assert!(apply_event(&mut state, &duplicate).is_err());
+        assert_eq!(chain(&state, "root"), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn content_and_metadata_updates_bump_revision() {
+        let mut state = replayed(&creation_log());
+        let revision = state[&id("b")].revision;
+
+        let content = NodeEvent::ContentUpdated {
+            node_id: id("b"),
+            content: serde_json::json!("edited"),
+            actor: "bob".to_string(),
+            timestamp: at(10),
+        };
+        apply_event(&mut state, &content).unwrap();
+        assert_eq!(state[&id("b")].content, serde_json::json!("edited"));
+        assert_eq!(state[&id("b")].revision, revision + 1);
+        assert_eq!(state[&id("b")].updated_at, at(10));
+
+        let metadata = NodeEvent::MetadataUpdated {
+            node_id: id("b"),
+            metadata: Some(serde_json::json!({"pinned": true})),
+            actor: "bob".to_string(),
+            timestamp: at(11),
+        };
+        apply_event(&mut state, &metadata).unwrap();
+        assert_eq!(
+            state[&id("b")].metadata,
+            Some(serde_json::json!({"pinned": true}))
+        );
+        assert_eq!(state[&id("b")].revision, revision + 2);
+
+        let missing = NodeEvent::content_updated(id("missing"), serde_json::json!(1), "bob");
+        assert!(apply_event(&mut state, &missing).is_err());
+    }
+
+    #[test]
+    fn moved_and_reordered_relink_siblings() {
+        let mut state = replayed(&creation_log());
+
+        let moved = NodeEvent::Moved {
+            node_id: id("c"),
+            new_parent: Some(id("a")),
+            after: None,
+            actor: "bob".to_string(),
+            timestamp: at(10),
+        };
+        apply_event(&mut state, &moved).unwrap();
+        assert_eq!(chain(&state, "root"), vec!["a", "b"]);
+        assert_eq!(chain(&state, "a"), vec!["c"]);
+        assert_eq!(state[&id("c")].updated_at, at(10));
+
+        let reordered = NodeEvent::Reordered {
+            node_id: id("a"),
+            after: Some(id("b")),
+            actor: "bob".to_string(),
+            timestamp: at(11),
+        };
+        apply_event(&mut state, &reordered).unwrap();
+        assert_eq!(chain(&state, "root"), vec!["b", "a"]);
+        assert!(is_valid(&state));
+
+        let into_own_child = NodeEvent::moved(id("a"), Some(id("c")), None, "bob");
+        assert!(apply_event(&mut state, &into_own_child).is_err());
+        assert_eq!(chain(&state, "a"), vec!["c"]);
+    }
+
+    #[test]
+    fn deleted_tombstones_subtree_and_restored_brings_it_back() {
+        let mut state = replayed(&creation_log());
+        apply_event(
+            &mut state,
+            &NodeEvent::moved(id("c"), Some(id("b")), None, "bob"),
+        )
+        .unwrap();
+
+        let deleted = NodeEvent::Deleted {
+            node_id: id("b"),
+            actor: "bob".to_string(),
+            timestamp: at(10),
+        };
+        apply_event(&mut state, &deleted).unwrap();
+        assert_eq!(chain(&state, "root"), vec!["a"]);
+        for name in ["b", "c"] {
+            assert_eq!(state[&id(name)].deleted_at, Some(at(10)));
+            assert_eq!(state[&id(name)].deleted_by.as_deref(), Some("bob"));
+        }
+        assert!(apply_event(&mut state, &deleted).is_err());
+
+        let restored = NodeEvent::Restored {
+            node_id: id("b"),
+            nodes: Vec::new(),
+            actor: "bob".to_string(),
+            timestamp: at(11),
+        };
+        apply_event(&mut state, &restored).unwrap();
+        assert_eq!(chain(&state, "root"), vec!["a", "b"]);
+        assert_eq!(chain(&state, "b"), vec!["c"]);
+        assert!(!state[&id("c")].is_deleted());
+        assert_eq!(state[&id("b")].updated_at, at(11));
+        assert!(is_valid(&state));
+    }
+
+    #[test]
+    fn restored_reinserts_purged_subtree_from_event() {
+        let mut state = replayed(&creation_log());
+        apply_event(
+            &mut state,
+            &NodeEvent::moved(id("c"), Some(id("b")), None, "bob"),
+        )
+        .unwrap();
+        let before_delete = vec![state[&id("b")].clone(), state[&id("c")].clone()];
+
+        apply_event(&mut state, &NodeEvent::deleted(id("b"), "bob")).unwrap();
+        state.retain(|_, node| !node.is_deleted());
+
+        let restored = NodeEvent::Restored {
+            node_id: id("b"),
+            nodes: before_delete,
+            actor: "bob".to_string(),
+            timestamp: at(11),
+        };
+        apply_event(&mut state, &restored).unwrap();
+        assert_eq!(chain(&state, "root"), vec!["a", "b"]);
+        assert_eq!(chain(&state, "b"), vec!["c"]);
+        assert!(is_valid(&state));
+
+        let without_root = NodeEvent::restored(id("z"), Vec::new(), "bob");
+        assert!(apply_event(&mut state, &without_root).is_err());
+    }
+
+    #[test]
+    fn replay_stops_at_first_rejected_event() {
+        let mut events = creation_log();
+        events.push(NodeEvent::deleted(id("missing"), "bob"));
+        events.push(NodeEvent::deleted(id("a"), "bob"));
+
+        let mut state = HashMap::new();
+        assert!(replay(&mut state, &events).is_err());
+        assert_eq!(chain(&state, "root"), vec!["a", "b", "c"]);
+    }
+}
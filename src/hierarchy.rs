@@ -0,0 +1,1170 @@
+//! Pure hierarchy mutations that return minimal change sets
+//!
+//! Every structural edit in an outline rewrites pointer fields on several
+//! neighbouring nodes. The operations here take the current state of a hierarchy,
+//! never mutate it, and return exactly the nodes whose `parent_id`,
+//! `before_sibling`, `next_sibling`, `root_id`, `order_key` or tombstone fields
+//! changed (with the revision bumped and `touch()` applied), so the storage layer
+//! can persist only what changed.
+//!
+//! The same change-set model backs the `root_id` utilities (recomputing roots for
+//! a subtree after a move, auditing stale values and producing a repair pass) and
+//! soft deletion. A tombstoned subtree root is unlinked from the live sibling
+//! chain but keeps its own `before_sibling` / `next_sibling` as a record of where
+//! it was, so it can be restored in place.
+
+use crate::ordering;
+use crate::{DatabaseError, Node, NodeId, NodeSpaceError, NodeSpaceResult, ValidationError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Result of a hierarchy mutation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HierarchyChangeSet {
+    /// Nodes that were created or had pointer fields rewritten
+    pub updated: Vec<Node>,
+    /// Nodes that were removed from the hierarchy
+    pub removed: Vec<NodeId>,
+}
+
+impl HierarchyChangeSet {
+    /// Check if the operation changed nothing
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.removed.is_empty()
+    }
+
+    /// Apply this change set to an in-memory list of nodes
+    ///
+    /// Updated nodes replace their previous version (or are appended if new) and
+    /// removed nodes are dropped.
+    pub fn apply_to(&self, nodes: &mut Vec<Node>) {
+        let removed: HashSet<&NodeId> = self.removed.iter().collect();
+        nodes.retain(|n| !removed.contains(&n.id));
+
+        let positions: HashMap<NodeId, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.id.clone(), i))
+            .collect();
+        for node in &self.updated {
+            match positions.get(&node.id) {
+                Some(&i) => nodes[i] = node.clone(),
+                None => nodes.push(node.clone()),
+            }
+        }
+    }
+}
+
+/// Insert a new node as the next sibling of `after`
+///
+/// The new node inherits the parent and root of `after`. Next to a top-level
+/// node it becomes a hierarchy root of its own.
+pub fn insert_after(
+    nodes: &[Node],
+    new_node: Node,
+    after: &NodeId,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    staging.insert_after(new_node, after)?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Insert a new node as the first child of `parent`
+pub fn insert_as_first_child(
+    nodes: &[Node],
+    new_node: Node,
+    parent: &NodeId,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    staging.insert_as_first_child(new_node, parent)?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Append new subtrees as the last children of `parent`
+///
+/// `new_nodes` must list every node after its parent, as the importers produce
+/// them. Nodes whose `parent_id` is `parent` or unset are appended after its
+/// existing children; the others are appended under their own parent. Sibling
+/// order follows the input order and `root_id` is taken from `parent`.
+pub fn append_subtrees(
+    nodes: &[Node],
+    parent: &NodeId,
+    new_nodes: Vec<Node>,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    if staging.get(parent)?.is_deleted() {
+        return Err(rule_violation(
+            "Cannot add children to a deleted node",
+            parent,
+            vec!["Restore the parent first".to_string()],
+        ));
+    }
+    for node in new_nodes {
+        let target = node.parent_id.clone().unwrap_or_else(|| parent.clone());
+        staging.append_child(node, &target)?;
+    }
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Move a node and its descendants to a new position
+///
+/// The node is placed under `new_parent` directly after `after`, or as the first
+/// child when `after` is `None`. Moving to `new_parent = None` turns the node into
+/// a hierarchy root whose `root_id` points to itself. `root_id` is rewritten on the
+/// whole subtree when the move crosses into a different hierarchy.
+pub fn move_subtree(
+    nodes: &[Node],
+    node_id: &NodeId,
+    new_parent: Option<&NodeId>,
+    after: Option<&NodeId>,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    staging.move_node(node_id, new_parent, after)?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Indent a node, making it the last child of its previous sibling
+pub fn indent(nodes: &[Node], node_id: &NodeId) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    let Some(new_parent) = staging.get(node_id)?.before_sibling.clone() else {
+        return Err(rule_violation(
+            "Cannot indent a node without a previous sibling",
+            node_id,
+            vec!["Indent is only available for nodes that are not the first child".to_string()],
+        ));
+    };
+    let after = staging.last_child(Some(&new_parent), node_id);
+    staging.move_node(node_id, Some(&new_parent), after.as_ref())?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Outdent a node, making it the next sibling of its current parent
+///
+/// Following siblings stay under the original parent.
+pub fn outdent(nodes: &[Node], node_id: &NodeId) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    let Some(parent) = staging.get(node_id)?.parent_id.clone() else {
+        return Err(rule_violation(
+            "Cannot outdent a node without a parent",
+            node_id,
+            vec!["Outdent is only available for nested nodes".to_string()],
+        ));
+    };
+    let grandparent = staging.get(&parent)?.parent_id.clone();
+    staging.move_node(node_id, grandparent.as_ref(), Some(&parent))?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Remove a node and all of its descendants
+///
+/// The surrounding siblings are relinked so the chain stays intact.
+pub fn remove_subtree(nodes: &[Node], node_id: &NodeId) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    let removed = staging.remove_subtree(node_id)?;
+    Ok(staging.finish(removed))
+}
+
+/// Soft-delete a node and all of its live descendants
+///
+/// Every node in the subtree gets `deleted_at` / `deleted_by`; descendants that
+/// were already deleted keep their original tombstone. The subtree root is
+/// unlinked from its live siblings but keeps its recorded sibling pointers.
+pub fn tombstone_subtree(
+    nodes: &[Node],
+    node_id: &NodeId,
+    actor: &str,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    staging.tombstone(node_id, actor, Utc::now())?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Restore a soft-deleted subtree at its original position
+///
+/// Clears the tombstone on every node deleted together with `node_id`. The
+/// subtree root is relinked after its recorded previous sibling, or before its
+/// recorded next sibling, when that neighbour is still live under the same
+/// parent; otherwise it becomes the first child.
+pub fn restore_subtree(nodes: &[Node], node_id: &NodeId) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    staging.restore(node_id)?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Drop tombstoned nodes, keeping only live ones
+pub fn filter_tombstones(nodes: Vec<Node>) -> Vec<Node> {
+    nodes.into_iter().filter(|n| !n.is_deleted()).collect()
+}
+
+/// Recompute `root_id` for a node and all of its descendants
+///
+/// Use after a move performed outside of this module. The root is resolved by
+/// following `parent_id`; a node without a parent becomes a hierarchy root whose
+/// `root_id` points to itself.
+pub fn recompute_root_ids(
+    nodes: &[Node],
+    subtree_root: &NodeId,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut staging = Staging::new(nodes);
+    staging.propagate_root(subtree_root)?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// A node whose stored `root_id` does not match its actual hierarchy root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StaleRootId {
+    pub node_id: NodeId,
+    pub stored_root: Option<NodeId>,
+    pub expected_root: NodeId,
+}
+
+/// Result of auditing the `root_id` denormalization of a set of nodes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootIdReport {
+    /// Number of nodes inspected
+    pub checked: usize,
+    /// Nodes with an incorrect `root_id`
+    pub stale: Vec<StaleRootId>,
+    /// Nodes whose root could not be determined (parent cycles)
+    pub unresolved: Vec<NodeId>,
+}
+
+impl RootIdReport {
+    /// Check if every resolvable node has a correct `root_id`
+    pub fn is_clean(&self) -> bool {
+        self.stale.is_empty()
+    }
+}
+
+/// Report which nodes have a stale `root_id`
+///
+/// Roots are resolved by following `parent_id`. When the chain leaves the given
+/// set, the stored `root_id` of the top-most known ancestor is trusted. As in
+/// [`crate::integrity::validate_hierarchy`], an unset `root_id` means the
+/// optimization is not configured for the node and is not stale; use
+/// [`recompute_root_ids`] to fill it in.
+pub fn audit_root_ids(nodes: &[Node]) -> RootIdReport {
+    let staging = Staging::new(nodes);
+    let mut report = RootIdReport {
+        checked: nodes.len(),
+        ..Default::default()
+    };
+
+    for node in nodes {
+        match staging.resolve_root(&node.id) {
+            Some(expected) if node.root_id.as_ref().is_some_and(|root| *root != expected) => {
+                report.stale.push(StaleRootId {
+                    node_id: node.id.clone(),
+                    stored_root: node.root_id.clone(),
+                    expected_root: expected,
+                })
+            }
+            Some(_) => {}
+            None => report.unresolved.push(node.id.clone()),
+        }
+    }
+
+    report
+}
+
+/// Rewrite every stale `root_id` in a set of nodes
+///
+/// Returns only the repaired nodes, suitable for a storage repair pass.
+pub fn repair_root_ids(nodes: &[Node]) -> NodeSpaceResult<HierarchyChangeSet> {
+    let report = audit_root_ids(nodes);
+    let mut staging = Staging::new(nodes);
+    for stale in report.stale {
+        staging.edit(&stale.node_id)?.root_id = Some(stale.expected_root);
+    }
+    Ok(staging.finish(Vec::new()))
+}
+
+fn rule_violation(rule: &str, node_id: &NodeId, resolution_steps: Vec<String>) -> NodeSpaceError {
+    ValidationError::BusinessRuleViolation {
+        rule: rule.to_string(),
+        context: serde_json::json!({ "node_id": node_id }),
+        resolution_steps,
+    }
+    .into()
+}
+
+/// Copy-on-write view over a hierarchy used to stage pointer edits
+///
+/// `parent_id` is indexed, so it must only change through [`Staging::insert`]
+/// and the linking operations, never through [`Staging::edit`].
+pub(crate) struct Staging<'a> {
+    order: Vec<&'a NodeId>,
+    original: HashMap<&'a NodeId, &'a Node>,
+    working: HashMap<NodeId, Node>,
+    inserted: Vec<NodeId>,
+    /// Every staged node, live or deleted, by `parent_id`
+    children: HashMap<Option<NodeId>, Vec<NodeId>>,
+}
+
+impl<'a> Staging<'a> {
+    pub(crate) fn new(nodes: impl IntoIterator<Item = &'a Node>) -> Self {
+        let nodes: Vec<&'a Node> = nodes.into_iter().collect();
+        let mut children: HashMap<Option<NodeId>, Vec<NodeId>> = HashMap::new();
+        for node in &nodes {
+            children
+                .entry(node.parent_id.clone())
+                .or_default()
+                .push(node.id.clone());
+        }
+        Self {
+            order: nodes.iter().map(|n| &n.id).collect(),
+            original: nodes.into_iter().map(|n| (&n.id, n)).collect(),
+            working: HashMap::new(),
+            inserted: Vec::new(),
+            children,
+        }
+    }
+
+    pub(crate) fn get(&self, id: &NodeId) -> NodeSpaceResult<&Node> {
+        self.working
+            .get(id)
+            .or_else(|| self.original.get(id).copied())
+            .ok_or_else(|| DatabaseError::not_found("Node", id.as_str()).into())
+    }
+
+    pub(crate) fn edit(&mut self, id: &NodeId) -> NodeSpaceResult<&mut Node> {
+        if !self.working.contains_key(id) {
+            let node = self.get(id)?.clone();
+            self.working.insert(id.clone(), node);
+        }
+        Ok(self.working.get_mut(id).expect("node staged above"))
+    }
+
+    /// Add a new, unlinked node to the staged set
+    pub(crate) fn insert(&mut self, mut node: Node) -> NodeSpaceResult<NodeId> {
+        if self.get(&node.id).is_ok() {
+            return Err(DatabaseError::ConstraintViolation {
+                constraint: "unique node id".to_string(),
+                table: "nodes".to_string(),
+                conflicting_value: node.id.to_string(),
+            }
+            .into());
+        }
+        node.before_sibling = None;
+        node.next_sibling = None;
+        let id = node.id.clone();
+        self.children
+            .entry(node.parent_id.clone())
+            .or_default()
+            .push(id.clone());
+        self.working.insert(id.clone(), node);
+        self.inserted.push(id.clone());
+        Ok(id)
+    }
+
+    /// Change `parent_id`, keeping the children index in sync
+    fn set_parent(&mut self, id: &NodeId, parent: Option<&NodeId>) -> NodeSpaceResult<()> {
+        let old = self.get(id)?.parent_id.clone();
+        if old.as_ref() == parent {
+            return Ok(());
+        }
+        if let Some(siblings) = self.children.get_mut(&old) {
+            siblings.retain(|sibling| sibling != id);
+        }
+        self.children
+            .entry(parent.cloned())
+            .or_default()
+            .push(id.clone());
+        self.edit(id)?.parent_id = parent.cloned();
+        Ok(())
+    }
+
+    pub(crate) fn insert_after(
+        &mut self,
+        new_node: Node,
+        after: &NodeId,
+    ) -> NodeSpaceResult<NodeId> {
+        let parent = self.get(after)?.parent_id.clone();
+        let id = self.insert(new_node)?;
+        let root = match parent.as_ref() {
+            None => Some(id.clone()),
+            Some(parent) => self.resolve_root(parent),
+        };
+
+        self.set_parent(&id, parent.as_ref())?;
+        self.edit(&id)?.root_id = root;
+        self.attach(&id, parent.as_ref(), Some(after))?;
+        Ok(id)
+    }
+
+    pub(crate) fn insert_as_first_child(
+        &mut self,
+        new_node: Node,
+        parent: &NodeId,
+    ) -> NodeSpaceResult<NodeId> {
+        self.get(parent)?;
+        let root = self.resolve_root(parent);
+        let id = self.insert(new_node)?;
+
+        self.set_parent(&id, Some(parent))?;
+        self.edit(&id)?.root_id = root;
+        self.attach(&id, Some(parent), None)?;
+        Ok(id)
+    }
+
+    /// Insert a new node as the last live child of `parent`
+    pub(crate) fn append_child(
+        &mut self,
+        new_node: Node,
+        parent: &NodeId,
+    ) -> NodeSpaceResult<NodeId> {
+        match self.last_child(Some(parent), &new_node.id) {
+            Some(last) => self.insert_after(new_node, &last),
+            None => self.insert_as_first_child(new_node, parent),
+        }
+    }
+
+    /// Unlink a subtree and return the IDs of every node in it
+    pub(crate) fn remove_subtree(&mut self, id: &NodeId) -> NodeSpaceResult<Vec<NodeId>> {
+        self.get(id)?;
+        let removed = self.subtree(id);
+        self.detach(id)?;
+        Ok(removed)
+    }
+
+    /// Mark a subtree as deleted and unlink its root from the live sibling chain
+    pub(crate) fn tombstone(
+        &mut self,
+        id: &NodeId,
+        actor: &str,
+        at: DateTime<Utc>,
+    ) -> NodeSpaceResult<()> {
+        let node = self.get(id)?;
+        if node.is_deleted() {
+            return Err(rule_violation(
+                "Node is already deleted",
+                id,
+                vec!["Restore the node before deleting it again".to_string()],
+            ));
+        }
+
+        // Relink the neighbours; the root keeps its own pointers as a record
+        let before = node.before_sibling.clone();
+        let next = node.next_sibling.clone();
+        if let Some(before) = before.as_ref().filter(|b| self.get(b).is_ok()) {
+            self.edit(before)?.next_sibling = next.clone();
+        }
+        if let Some(next) = next.as_ref().filter(|n| self.get(n).is_ok()) {
+            self.edit(next)?.before_sibling = before.clone();
+        }
+
+        for member in self.subtree(id) {
+            let node = self.edit(&member)?;
+            if !node.is_deleted() {
+                node.deleted_at = Some(at);
+                node.deleted_by = Some(actor.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Clear a subtree's tombstone and relink its root at the recorded position
+    pub(crate) fn restore(&mut self, id: &NodeId) -> NodeSpaceResult<()> {
+        let node = self.get(id)?;
+        let Some(stamp) = node.deleted_at else {
+            return Err(rule_violation(
+                "Node is not deleted",
+                id,
+                vec!["Only tombstoned nodes can be restored".to_string()],
+            ));
+        };
+        let deleted_by = node.deleted_by.clone();
+        let parent = node.parent_id.clone();
+        let recorded_before = node.before_sibling.clone();
+        let recorded_next = node.next_sibling.clone();
+        if let Some(parent) = parent.as_ref() {
+            if self.get(parent).is_ok_and(Node::is_deleted) {
+                return Err(rule_violation(
+                    "Cannot restore a node under a deleted parent",
+                    id,
+                    vec![format!("Restore {} first", parent)],
+                ));
+            }
+        }
+
+        // Only nodes deleted by the same operation come back
+        for member in self.subtree(id) {
+            let node = self.edit(&member)?;
+            if node.deleted_at == Some(stamp) && node.deleted_by == deleted_by {
+                node.deleted_at = None;
+                node.deleted_by = None;
+            }
+        }
+
+        let live_sibling = |candidate: Option<NodeId>| {
+            candidate.filter(|c| {
+                self.get(c)
+                    .is_ok_and(|n| !n.is_deleted() && n.parent_id == parent)
+            })
+        };
+        let before = live_sibling(recorded_before);
+        let next = live_sibling(recorded_next);
+
+        let node = self.edit(id)?;
+        node.before_sibling = None;
+        node.next_sibling = None;
+        match (before, next) {
+            (Some(before), _) => self.attach(id, parent.as_ref(), Some(&before))?,
+            (None, Some(next)) => match self.get(&next)?.before_sibling.clone() {
+                Some(before) => self.attach(id, parent.as_ref(), Some(&before))?,
+                None if parent.is_some() => self.attach(id, parent.as_ref(), None)?,
+                // Top-level nodes have no first-child slot; link in front of `next`
+                None => {
+                    self.edit(&next)?.before_sibling = Some(id.clone());
+                    self.edit(id)?.next_sibling = Some(next);
+                    self.assign_order_key(id)?;
+                }
+            },
+            (None, None) => self.attach(id, parent.as_ref(), None)?,
+        }
+
+        if self.resolve_root(id) != self.get(id)?.root_id {
+            self.propagate_root(id)?;
+        }
+        Ok(())
+    }
+
+    /// Live children of a parent in the staged state, in staging order
+    fn live_children<'s>(
+        &'s self,
+        parent: Option<&NodeId>,
+    ) -> impl DoubleEndedIterator<Item = &'s Node> {
+        self.children
+            .get(&parent.cloned())
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.get(id).ok())
+            .filter(|n| !n.is_deleted())
+    }
+
+    /// Live children of a parent in the staged state, unordered
+    pub(crate) fn children_of(&self, parent: Option<&NodeId>) -> Vec<NodeId> {
+        self.live_children(parent).map(|n| n.id.clone()).collect()
+    }
+
+    fn first_child(&self, parent: Option<&NodeId>, excluding: &NodeId) -> Option<NodeId> {
+        self.live_children(parent)
+            .find(|n| &n.id != excluding && n.before_sibling.is_none())
+            .map(|n| n.id.clone())
+    }
+
+    fn last_child(&self, parent: Option<&NodeId>, excluding: &NodeId) -> Option<NodeId> {
+        // Appended children are indexed last, so search from the end
+        self.live_children(parent)
+            .rev()
+            .find(|n| &n.id != excluding && n.next_sibling.is_none())
+            .map(|n| n.id.clone())
+    }
+
+    /// A node and all of its descendants in the staged state
+    pub(crate) fn subtree(&self, id: &NodeId) -> Vec<NodeId> {
+        let mut visited = HashSet::new();
+        let mut result = Vec::new();
+        let mut stack = vec![id.clone()];
+        while let Some(current) = stack.pop() {
+            if !visited.insert(current.clone()) {
+                continue;
+            }
+            if let Some(kids) = self.children.get(&Some(current.clone())) {
+                stack.extend(kids.iter().rev().cloned());
+            }
+            result.push(current);
+        }
+        result
+    }
+
+    /// Resolve the hierarchy root of a node by following `parent_id`
+    ///
+    /// Falls back to the stored `root_id` of the top-most known ancestor when the
+    /// chain leaves the staged set.
+    pub(crate) fn resolve_root(&self, id: &NodeId) -> Option<NodeId> {
+        let mut visited = HashSet::new();
+        let mut current = self.get(id).ok()?;
+        loop {
+            if !visited.insert(&current.id) {
+                return None;
+            }
+            match current.parent_id.as_ref() {
+                None => return Some(current.id.clone()),
+                Some(parent) => match self.get(parent) {
+                    Ok(node) => current = node,
+                    Err(_) => return current.root_id.clone(),
+                },
+            }
+        }
+    }
+
+    /// Unlink a node from its sibling chain
+    fn detach(&mut self, id: &NodeId) -> NodeSpaceResult<()> {
+        let node = self.get(id)?;
+        let before = node.before_sibling.clone();
+        let next = node.next_sibling.clone();
+
+        if let Some(before) = before.as_ref().filter(|b| self.get(b).is_ok()) {
+            self.edit(before)?.next_sibling = next.clone();
+        }
+        if let Some(next) = next.as_ref().filter(|n| self.get(n).is_ok()) {
+            self.edit(next)?.before_sibling = before.clone();
+        }
+
+        let node = self.edit(id)?;
+        node.before_sibling = None;
+        node.next_sibling = None;
+        Ok(())
+    }
+
+    /// Link a detached node into the sibling chain of `parent`
+    ///
+    /// With `after = None` the node becomes the first child. Top-level nodes
+    /// inserted without an anchor are left unlinked, since independent roots do not
+    /// share a sibling chain.
+    fn attach(
+        &mut self,
+        id: &NodeId,
+        parent: Option<&NodeId>,
+        after: Option<&NodeId>,
+    ) -> NodeSpaceResult<()> {
+        match after {
+            Some(after) => {
+                let anchor = self.get(after)?;
+                if anchor.parent_id.as_ref() != parent || anchor.is_deleted() {
+                    return Err(rule_violation(
+                        "Anchor sibling must be a live child of the target parent",
+                        after,
+                        vec!["Pass an anchor that is a child of the new parent".to_string()],
+                    ));
+                }
+                let next = anchor.next_sibling.clone();
+
+                self.edit(after)?.next_sibling = Some(id.clone());
+                if let Some(next) = next.as_ref().filter(|n| self.get(n).is_ok()) {
+                    self.edit(next)?.before_sibling = Some(id.clone());
+                }
+                let node = self.edit(id)?;
+                node.before_sibling = Some(after.clone());
+                node.next_sibling = next;
+            }
+            None if parent.is_some() => {
+                let first = self.first_child(parent, id);
+                if let Some(first) = first.as_ref() {
+                    self.edit(first)?.before_sibling = Some(id.clone());
+                }
+                let node = self.edit(id)?;
+                node.before_sibling = None;
+                node.next_sibling = first;
+            }
+            None => return Ok(()),
+        }
+        self.assign_order_key(id)
+    }
+
+    /// Give a freshly linked node an order key between its neighbours' keys
+    ///
+    /// Only applies when the neighbours use order keys, and a key that already
+    /// sorts between them is kept. If their keys are out of order the node keeps
+    /// its current key and a rebalance is needed.
+    fn assign_order_key(&mut self, id: &NodeId) -> NodeSpaceResult<()> {
+        let node = self.get(id)?;
+        let key_of = |neighbour: &Option<NodeId>| {
+            neighbour
+                .as_ref()
+                .and_then(|n| self.get(n).ok())
+                .and_then(|n| n.order_key.clone())
+        };
+        let before_key = key_of(&node.before_sibling);
+        let next_key = key_of(&node.next_sibling);
+        if before_key.is_none() && next_key.is_none() {
+            return Ok(());
+        }
+        if let Some(key) = node.order_key.as_ref() {
+            let fits = before_key.as_ref().is_none_or(|before| before < key)
+                && next_key.as_ref().is_none_or(|next| key < next);
+            if fits && ordering::validate_order_key(key).is_ok() {
+                return Ok(());
+            }
+        }
+
+        if let Ok(key) = ordering::key_between(before_key.as_deref(), next_key.as_deref()) {
+            self.edit(id)?.order_key = Some(key);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn move_node(
+        &mut self,
+        id: &NodeId,
+        new_parent: Option<&NodeId>,
+        after: Option<&NodeId>,
+    ) -> NodeSpaceResult<()> {
+        if self.get(id)?.is_deleted() {
+            return Err(rule_violation(
+                "Cannot move a deleted node",
+                id,
+                vec!["Restore the node before moving it".to_string()],
+            ));
+        }
+        let subtree = self.subtree(id);
+        if let Some(target) = [new_parent, after]
+            .into_iter()
+            .flatten()
+            .find(|t| subtree.contains(t))
+        {
+            return Err(rule_violation(
+                "Cannot move a node into its own subtree",
+                id,
+                vec![format!(
+                    "Choose a target outside the subtree of {} (got {})",
+                    id, target
+                )],
+            ));
+        }
+        if let Some(parent) = new_parent {
+            if self.get(parent)?.is_deleted() {
+                return Err(rule_violation(
+                    "Cannot move a node under a deleted parent",
+                    id,
+                    vec![format!("Restore {} first", parent)],
+                ));
+            }
+        }
+
+        let old_root = self.resolve_root(id);
+        self.detach(id)?;
+        self.set_parent(id, new_parent)?;
+        self.attach(id, new_parent, after)?;
+
+        if self.resolve_root(id) != old_root {
+            self.propagate_root(id)?;
+        }
+        Ok(())
+    }
+
+    /// Point `root_id` of a node and all of its descendants at their resolved root
+    pub(crate) fn propagate_root(&mut self, id: &NodeId) -> NodeSpaceResult<()> {
+        let root = self.resolve_root(id);
+        for member in self.subtree(id) {
+            self.edit(&member)?.root_id = root.clone();
+        }
+        Ok(())
+    }
+
+    /// Collect the staged nodes that actually changed, bumping each revision
+    pub(crate) fn finish(mut self, removed: Vec<NodeId>) -> HierarchyChangeSet {
+        let removed_set: HashSet<&NodeId> = removed.iter().collect();
+        let mut updated = Vec::new();
+
+        for id in self.order.iter().copied().chain(self.inserted.iter()) {
+            if removed_set.contains(id) {
+                continue;
+            }
+            let Some(mut node) = self.working.remove(id) else {
+                continue;
+            };
+            match self.original.get(id) {
+                Some(original) if tracked_fields_differ(original, &node) => {
+                    node.bump_revision();
+                    updated.push(node);
+                }
+                Some(_) => {}
+                None => {
+                    node.touch();
+                    updated.push(node);
+                }
+            }
+        }
+
+        HierarchyChangeSet { updated, removed }
+    }
+}
+
+/// Compare the fields hierarchy operations are allowed to rewrite
+fn tracked_fields_differ(a: &Node, b: &Node) -> bool {
+    a.parent_id != b.parent_id
+        || a.before_sibling != b.before_sibling
+        || a.next_sibling != b.next_sibling
+        || a.root_id != b.root_id
+        || a.order_key != b.order_key
+        || a.deleted_at != b.deleted_at
+        || a.deleted_by != b.deleted_by
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::validate_hierarchy;
+
+    fn node(id: &str) -> Node {
+        Node::with_id(
+            NodeId::from_string(id.to_string()),
+            "text".to_string(),
+            serde_json::json!(id),
+        )
+    }
+
+    fn root(id: &str) -> Node {
+        let mut node = node(id);
+        node.mark_as_hierarchy_root();
+        node
+    }
+
+    fn applied(nodes: &[Node], changes: &HierarchyChangeSet) -> Vec<Node> {
+        let mut nodes = nodes.to_vec();
+        changes.apply_to(&mut nodes);
+        nodes
+    }
+
+    fn find<'n>(nodes: &'n [Node], id: &str) -> &'n Node {
+        nodes.iter().find(|n| n.id.as_str() == id).unwrap()
+    }
+
+    #[test]
+    fn insert_after_root_creates_own_root() {
+        let nodes = vec![root("a")];
+        let changes = insert_after(&nodes, node("b"), &nodes[0].id).unwrap();
+        let nodes = applied(&nodes, &changes);
+
+        let b = find(&nodes, "b");
+        assert_eq!(b.parent_id, None);
+        assert_eq!(b.root_id, Some(b.id.clone()));
+        assert_eq!(find(&nodes, "a").next_sibling, Some(b.id.clone()));
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+
+    #[test]
+    fn insert_after_child_inherits_parent_root() {
+        let top = root("top");
+        let mut child = node("child");
+        child.parent_id = Some(top.id.clone());
+        child.root_id = Some(top.id.clone());
+        let nodes = vec![top.clone(), child.clone()];
+
+        let changes = insert_after(&nodes, node("new"), &child.id).unwrap();
+        let nodes = applied(&nodes, &changes);
+
+        let new = find(&nodes, "new");
+        assert_eq!(new.parent_id, Some(top.id.clone()));
+        assert_eq!(new.root_id, Some(top.id.clone()));
+        assert_eq!(new.before_sibling, Some(child.id.clone()));
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+
+    fn find_mut<'n>(nodes: &'n mut [Node], id: &str) -> &'n mut Node {
+        nodes.iter_mut().find(|n| n.id.as_str() == id).unwrap()
+    }
+
+    fn id(id: &str) -> NodeId {
+        NodeId::from_string(id.to_string())
+    }
+
+    /// root ─┬─ a ─── a1
+    ///       ├─ b
+    ///       └─ c
+    fn outline() -> Vec<Node> {
+        let mut nodes = vec![root("root")];
+        for (child, parent) in [("a", "root"), ("b", "root"), ("c", "root"), ("a1", "a")] {
+            let mut node = node(child);
+            node.parent_id = Some(id(parent));
+            node.root_id = Some(id("root"));
+            nodes.push(node);
+        }
+        nodes[1].next_sibling = Some(id("b"));
+        nodes[2].before_sibling = Some(id("a"));
+        nodes[2].next_sibling = Some(id("c"));
+        nodes[3].before_sibling = Some(id("b"));
+        nodes
+    }
+
+    /// Children of `parent` by walking the sibling chain from its first child
+    fn chain(nodes: &[Node], parent: &str) -> Vec<String> {
+        let children: Vec<&Node> = nodes
+            .iter()
+            .filter(|n| n.parent_id == Some(id(parent)) && !n.is_deleted())
+            .collect();
+        let mut current = children
+            .iter()
+            .find(|n| n.before_sibling.is_none())
+            .copied();
+        let mut result = Vec::new();
+        while let Some(node) = current {
+            result.push(node.id.to_string());
+            current = node
+                .next_sibling
+                .as_ref()
+                .and_then(|next| children.iter().find(|n| &n.id == next).copied());
+        }
+        assert_eq!(
+            result.len(),
+            children.len(),
+            "broken chain under {}",
+            parent
+        );
+        result
+    }
+
+    fn updated_ids(changes: &HierarchyChangeSet) -> Vec<&str> {
+        let mut ids: Vec<&str> = changes.updated.iter().map(|n| n.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    fn assert_revisions_bumped(before: &[Node], changes: &HierarchyChangeSet) {
+        for node in &changes.updated {
+            if let Some(original) = before.iter().find(|n| n.id == node.id) {
+                assert_eq!(node.revision, original.revision + 1, "{}", node.id);
+            }
+        }
+    }
+
+    #[test]
+    fn insert_as_first_child_links_before_existing_children() {
+        let nodes = outline();
+        let changes = insert_as_first_child(&nodes, node("new"), &id("root")).unwrap();
+        assert_eq!(updated_ids(&changes), vec!["a", "new"]);
+        assert_revisions_bumped(&nodes, &changes);
+
+        let nodes = applied(&nodes, &changes);
+        assert_eq!(chain(&nodes, "root"), vec!["new", "a", "b", "c"]);
+        assert_eq!(find(&nodes, "new").root_id, Some(id("root")));
+        assert!(validate_hierarchy(&nodes).is_empty());
+
+        let changes = insert_as_first_child(&nodes, node("only"), &id("b")).unwrap();
+        assert_eq!(updated_ids(&changes), vec!["only"]);
+        assert!(insert_as_first_child(&nodes, node("x"), &id("missing")).is_err());
+        assert!(insert_as_first_child(&nodes, node("a1"), &id("b")).is_err());
+    }
+
+    #[test]
+    fn append_subtrees_appends_in_input_order() {
+        let nodes = outline();
+        let mut nested = node("n1");
+        nested.parent_id = Some(id("x"));
+        let new_nodes = vec![node("x"), nested, node("y")];
+        let changes = append_subtrees(&nodes, &id("root"), new_nodes).unwrap();
+        assert_eq!(updated_ids(&changes), vec!["c", "n1", "x", "y"]);
+        assert_revisions_bumped(&nodes, &changes);
+
+        let nodes = applied(&nodes, &changes);
+        assert_eq!(chain(&nodes, "root"), vec!["a", "b", "c", "x", "y"]);
+        assert_eq!(chain(&nodes, "x"), vec!["n1"]);
+        assert_eq!(find(&nodes, "n1").root_id, Some(id("root")));
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+
+    #[test]
+    fn move_subtree_relinks_both_positions() {
+        let nodes = outline();
+        let changes = move_subtree(&nodes, &id("a"), Some(&id("root")), Some(&id("c"))).unwrap();
+        assert_eq!(updated_ids(&changes), vec!["a", "b", "c"]);
+        assert_revisions_bumped(&nodes, &changes);
+        let moved = applied(&nodes, &changes);
+        assert_eq!(chain(&moved, "root"), vec!["b", "c", "a"]);
+        assert!(validate_hierarchy(&moved).is_empty());
+
+        // Into another parent, as first child
+        let changes = move_subtree(&nodes, &id("c"), Some(&id("a")), None).unwrap();
+        let moved = applied(&nodes, &changes);
+        assert_eq!(chain(&moved, "root"), vec!["a", "b"]);
+        assert_eq!(chain(&moved, "a"), vec!["c", "a1"]);
+        assert!(validate_hierarchy(&moved).is_empty());
+    }
+
+    #[test]
+    fn move_subtree_to_top_level_makes_a_new_root() {
+        let nodes = outline();
+        let changes = move_subtree(&nodes, &id("a"), None, None).unwrap();
+        let moved = applied(&nodes, &changes);
+        assert_eq!(find(&moved, "a").parent_id, None);
+        assert_eq!(find(&moved, "a").root_id, Some(id("a")));
+        assert_eq!(find(&moved, "a1").root_id, Some(id("a")));
+        assert_eq!(chain(&moved, "root"), vec!["b", "c"]);
+        assert!(validate_hierarchy(&moved).is_empty());
+    }
+
+    #[test]
+    fn move_subtree_rejects_its_own_subtree_and_deleted_targets() {
+        let nodes = outline();
+        assert!(move_subtree(&nodes, &id("a"), Some(&id("a1")), None).is_err());
+        assert!(move_subtree(&nodes, &id("a"), Some(&id("a")), None).is_err());
+        assert!(move_subtree(&nodes, &id("root"), Some(&id("b")), None).is_err());
+        // The anchor must be a child of the new parent
+        assert!(move_subtree(&nodes, &id("c"), Some(&id("a")), Some(&id("b"))).is_err());
+
+        let deleted = applied(&nodes, &tombstone_subtree(&nodes, &id("b"), "me").unwrap());
+        assert!(move_subtree(&deleted, &id("c"), Some(&id("b")), None).is_err());
+        assert!(move_subtree(&deleted, &id("b"), Some(&id("a")), None).is_err());
+    }
+
+    #[test]
+    fn indent_and_outdent() {
+        let nodes = outline();
+        let changes = indent(&nodes, &id("b")).unwrap();
+        assert_revisions_bumped(&nodes, &changes);
+        let indented = applied(&nodes, &changes);
+        assert_eq!(chain(&indented, "root"), vec!["a", "c"]);
+        assert_eq!(chain(&indented, "a"), vec!["a1", "b"]);
+        assert!(validate_hierarchy(&indented).is_empty());
+        assert!(indent(&nodes, &id("a")).is_err());
+
+        let changes = outdent(&indented, &id("a1")).unwrap();
+        let outdented = applied(&indented, &changes);
+        assert_eq!(chain(&outdented, "root"), vec!["a", "a1", "c"]);
+        // Following siblings stay under the original parent
+        assert_eq!(chain(&outdented, "a"), vec!["b"]);
+        assert!(validate_hierarchy(&outdented).is_empty());
+        assert!(outdent(&nodes, &id("root")).is_err());
+    }
+
+    #[test]
+    fn remove_subtree_relinks_neighbours() {
+        let nodes = outline();
+        let changes = remove_subtree(&nodes, &id("a")).unwrap();
+        let mut removed: Vec<&str> = changes.removed.iter().map(NodeId::as_str).collect();
+        removed.sort();
+        assert_eq!(removed, vec!["a", "a1"]);
+        assert_eq!(updated_ids(&changes), vec!["b"]);
+        assert_revisions_bumped(&nodes, &changes);
+
+        let remaining = applied(&nodes, &changes);
+        assert_eq!(remaining.len(), 3);
+        assert_eq!(chain(&remaining, "root"), vec!["b", "c"]);
+        assert!(validate_hierarchy(&remaining).is_empty());
+        assert!(remove_subtree(&nodes, &id("missing")).is_err());
+    }
+
+    #[test]
+    fn recompute_root_ids_rewrites_a_subtree() {
+        let mut nodes = outline();
+        for node in nodes.iter_mut().filter(|n| n.id != id("root")) {
+            node.root_id = None;
+        }
+        let changes = recompute_root_ids(&nodes, &id("a")).unwrap();
+        assert_eq!(updated_ids(&changes), vec!["a", "a1"]);
+        assert_revisions_bumped(&nodes, &changes);
+        let nodes = applied(&nodes, &changes);
+        assert_eq!(find(&nodes, "a1").root_id, Some(id("root")));
+        assert_eq!(find(&nodes, "b").root_id, None);
+
+        let changes = recompute_root_ids(&nodes, &id("a")).unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn audit_and_repair_agree_with_validation() {
+        let mut nodes = outline();
+        find_mut(&mut nodes, "a1").root_id = Some(id("a"));
+        find_mut(&mut nodes, "b").root_id = None;
+
+        let report = audit_root_ids(&nodes);
+        assert_eq!(report.checked, 5);
+        assert_eq!(
+            report.stale,
+            vec![StaleRootId {
+                node_id: id("a1"),
+                stored_root: Some(id("a")),
+                expected_root: id("root"),
+            }]
+        );
+        assert!(!report.is_clean());
+        assert_eq!(validate_hierarchy(&nodes).len(), 1);
+
+        let changes = repair_root_ids(&nodes).unwrap();
+        assert_eq!(updated_ids(&changes), vec!["a1"]);
+        let nodes = applied(&nodes, &changes);
+        assert!(audit_root_ids(&nodes).is_clean());
+        assert!(validate_hierarchy(&nodes).is_empty());
+        assert_eq!(find(&nodes, "b").root_id, None);
+    }
+
+    #[test]
+    fn audit_reports_parent_cycles_as_unresolved() {
+        let mut nodes = vec![node("p"), node("q")];
+        nodes[0].parent_id = Some(id("q"));
+        nodes[1].parent_id = Some(id("p"));
+        nodes[0].root_id = Some(id("p"));
+        let report = audit_root_ids(&nodes);
+        assert!(report.is_clean());
+        assert_eq!(report.unresolved, vec![id("p"), id("q")]);
+    }
+
+    fn tombstoned(nodes: &[Node], target: &str, actor: &str) -> Vec<Node> {
+        let changes = tombstone_subtree(nodes, &id(target), actor).unwrap();
+        assert_revisions_bumped(nodes, &changes);
+        applied(nodes, &changes)
+    }
+
+    fn restored(nodes: &[Node], target: &str) -> Vec<Node> {
+        let changes = restore_subtree(nodes, &id(target)).unwrap();
+        assert_revisions_bumped(nodes, &changes);
+        applied(nodes, &changes)
+    }
+
+    #[test]
+    fn tombstone_and_restore_round_trip() {
+        let nodes = outline();
+        for target in ["a", "b", "c"] {
+            let deleted = tombstoned(&nodes, target, "alice");
+            let remaining: Vec<String> = ["a", "b", "c"]
+                .into_iter()
+                .filter(|n| *n != target)
+                .map(String::from)
+                .collect();
+            assert_eq!(chain(&deleted, "root"), remaining);
+            assert!(find(&deleted, target).is_deleted());
+            assert_eq!(find(&deleted, "a1").is_deleted(), target == "a");
+            if target == "a" {
+                assert!(restore_subtree(&deleted, &id("a1")).is_err());
+            }
+
+            let back = restored(&deleted, target);
+            assert_eq!(chain(&back, "root"), vec!["a", "b", "c"]);
+            assert_eq!(chain(&back, "a"), vec!["a1"]);
+            assert!(back.iter().all(|n| !n.is_deleted()));
+            assert!(validate_hierarchy(&back).is_empty());
+        }
+        assert!(restore_subtree(&nodes, &id("a")).is_err());
+    }
+
+    #[test]
+    fn restore_keeps_nodes_deleted_by_another_operation() {
+        let nodes = tombstoned(&outline(), "a1", "bob");
+        let nodes = tombstoned(&nodes, "a", "alice");
+        assert!(tombstone_subtree(&nodes, &id("a"), "alice").is_err());
+
+        let nodes = restored(&nodes, "a");
+        assert_eq!(chain(&nodes, "root"), vec!["a", "b", "c"]);
+        assert!(find(&nodes, "a1").is_deleted());
+        assert_eq!(find(&nodes, "a1").deleted_by.as_deref(), Some("bob"));
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+
+    #[test]
+    fn restore_top_level_node_before_its_recorded_next_sibling() {
+        let mut nodes = vec![root("x"), root("y"), root("z")];
+        nodes[0].next_sibling = Some(id("y"));
+        nodes[1].before_sibling = Some(id("x"));
+        nodes[1].next_sibling = Some(id("z"));
+        nodes[2].before_sibling = Some(id("y"));
+
+        // y's recorded previous sibling x is deleted as well, so only z is live
+        let nodes = tombstoned(&nodes, "y", "alice");
+        let nodes = tombstoned(&nodes, "x", "alice");
+        assert_eq!(find(&nodes, "z").before_sibling, None);
+
+        let nodes = restored(&nodes, "y");
+        assert_eq!(find(&nodes, "y").next_sibling, Some(id("z")));
+        assert_eq!(find(&nodes, "z").before_sibling, Some(id("y")));
+
+        // x was unlinked while y was already gone, so it returns in front of z
+        let nodes = restored(&nodes, "x");
+        assert_eq!(find(&nodes, "y").next_sibling, Some(id("x")));
+        assert_eq!(find(&nodes, "x").next_sibling, Some(id("z")));
+        assert_eq!(find(&nodes, "z").before_sibling, Some(id("x")));
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+}
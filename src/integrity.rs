@@ -0,0 +1,447 @@
+//! Structural integrity checks for node hierarchies
+//!
+//! `Node` carries four pointer fields (`parent_id`, `before_sibling`, `next_sibling`,
+//! `root_id`) that must agree with each other. These checks run over a complete
+//! hierarchy (for example the result of a `root_id` query) and report every
+//! inconsistency as a structured [`ValidationError`].
+
+use crate::{Node, NodeId, NodeSpaceResult, ValidationError};
+use std::collections::{HashMap, HashSet};
+
+/// Validate the pointer fields of a set of nodes against each other
+///
+/// The set is assumed to be closed: any `parent_id`, `before_sibling`,
+/// `next_sibling` or `root_id` that points outside of it is reported as a
+/// dangling reference, and an ID that appears more than once as a duplicate. An
+/// empty result means the hierarchy is consistent.
+///
+/// Top-level nodes (no `parent_id`) are not required to form a single sibling
+/// chain, since independent hierarchy roots such as date nodes are never linked.
+/// Tombstoned nodes are skipped: a deleted subtree keeps its recorded sibling
+/// pointers but is no longer part of the live hierarchy.
+pub fn validate_hierarchy(nodes: &[Node]) -> Vec<ValidationError> {
+    if nodes.iter().any(Node::is_deleted) {
+        let live: Vec<Node> = nodes.iter().filter(|n| !n.is_deleted()).cloned().collect();
+        return validate_hierarchy(&live);
+    }
+
+    let by_id: HashMap<&NodeId, &Node> = nodes.iter().map(|n| (&n.id, n)).collect();
+    let mut errors = Vec::new();
+
+    check_duplicate_ids(nodes, &mut errors);
+    check_dangling_references(nodes, &by_id, &mut errors);
+    check_sibling_links(nodes, &by_id, &mut errors);
+
+    for cycle in find_cycles(nodes, |n| n.next_sibling.as_ref(), &by_id) {
+        errors.push(ValidationError::SiblingCycle { node_ids: cycle });
+    }
+    let parent_cycles = find_cycles(nodes, |n| n.parent_id.as_ref(), &by_id);
+    let in_parent_cycle: HashSet<NodeId> = parent_cycles.iter().flatten().cloned().collect();
+    for cycle in parent_cycles {
+        errors.push(ValidationError::ParentCycle { node_ids: cycle });
+    }
+
+    check_chain_ends(nodes, &by_id, &mut errors);
+    check_root_ids(nodes, &by_id, &in_parent_cycle, &mut errors);
+
+    errors
+}
+
+/// Validate a hierarchy, returning the first inconsistency as an error
+pub fn ensure_valid_hierarchy(nodes: &[Node]) -> NodeSpaceResult<()> {
+    match validate_hierarchy(nodes).into_iter().next() {
+        Some(error) => Err(error.into()),
+        None => Ok(()),
+    }
+}
+
+/// Resolve the top-most ancestor of a node by following `parent_id`
+///
+/// Returns `None` when the chain leaves the set or loops back on itself, in which
+/// case the true root cannot be determined.
+pub(crate) fn resolve_root<'a>(
+    node: &'a Node,
+    by_id: &HashMap<&NodeId, &'a Node>,
+) -> Option<&'a NodeId> {
+    let mut visited = HashSet::new();
+    let mut current = node;
+    loop {
+        if !visited.insert(&current.id) {
+            return None;
+        }
+        match current.parent_id.as_ref() {
+            None => return Some(&current.id),
+            Some(parent) => current = by_id.get(parent)?,
+        }
+    }
+}
+
+fn check_duplicate_ids(nodes: &[Node], errors: &mut Vec<ValidationError>) {
+    let mut counts: HashMap<&NodeId, usize> = HashMap::new();
+    for node in nodes {
+        *counts.entry(&node.id).or_default() += 1;
+    }
+    let mut reported = HashSet::new();
+    for node in nodes {
+        let count = counts[&node.id];
+        if count > 1 && reported.insert(&node.id) {
+            errors.push(ValidationError::DuplicateNodeId {
+                node_id: node.id.clone(),
+                count,
+            });
+        }
+    }
+}
+
+fn check_dangling_references(
+    nodes: &[Node],
+    by_id: &HashMap<&NodeId, &Node>,
+    errors: &mut Vec<ValidationError>,
+) {
+    for node in nodes {
+        let pointers = [
+            ("parent_id", &node.parent_id),
+            ("before_sibling", &node.before_sibling),
+            ("next_sibling", &node.next_sibling),
+            ("root_id", &node.root_id),
+        ];
+        for (field, target) in pointers {
+            if let Some(target) = target {
+                if !by_id.contains_key(target) {
+                    errors.push(ValidationError::DanglingReference {
+                        node_id: node.id.clone(),
+                        field: field.to_string(),
+                        missing_id: target.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn check_sibling_links(
+    nodes: &[Node],
+    by_id: &HashMap<&NodeId, &Node>,
+    errors: &mut Vec<ValidationError>,
+) {
+    let mut mismatched_pairs = HashSet::new();
+
+    for node in nodes {
+        if let Some(next) = node.next_sibling.as_ref().and_then(|id| by_id.get(id)) {
+            if next.before_sibling.as_ref() != Some(&node.id) {
+                errors.push(ValidationError::AsymmetricSiblingLink {
+                    node_id: node.id.clone(),
+                    sibling_id: next.id.clone(),
+                    field: "next_sibling".to_string(),
+                    back_link: next.before_sibling.clone(),
+                });
+            }
+            check_same_parent(node, next, &mut mismatched_pairs, errors);
+        }
+
+        if let Some(before) = node.before_sibling.as_ref().and_then(|id| by_id.get(id)) {
+            if before.next_sibling.as_ref() != Some(&node.id) {
+                errors.push(ValidationError::AsymmetricSiblingLink {
+                    node_id: node.id.clone(),
+                    sibling_id: before.id.clone(),
+                    field: "before_sibling".to_string(),
+                    back_link: before.next_sibling.clone(),
+                });
+            }
+            check_same_parent(before, node, &mut mismatched_pairs, errors);
+        }
+    }
+}
+
+fn check_same_parent<'a>(
+    first: &'a Node,
+    second: &'a Node,
+    reported: &mut HashSet<(&'a NodeId, &'a NodeId)>,
+    errors: &mut Vec<ValidationError>,
+) {
+    if first.parent_id != second.parent_id && reported.insert((&first.id, &second.id)) {
+        errors.push(ValidationError::SiblingParentMismatch {
+            node_id: first.id.clone(),
+            sibling_id: second.id.clone(),
+            node_parent: first.parent_id.clone(),
+            sibling_parent: second.parent_id.clone(),
+        });
+    }
+}
+
+fn check_chain_ends(
+    nodes: &[Node],
+    by_id: &HashMap<&NodeId, &Node>,
+    errors: &mut Vec<ValidationError>,
+) {
+    let mut parents: Vec<&NodeId> = Vec::new();
+    let mut firsts: HashMap<&NodeId, Vec<NodeId>> = HashMap::new();
+    let mut lasts: HashMap<&NodeId, Vec<NodeId>> = HashMap::new();
+
+    for node in nodes {
+        let Some(parent) = node.parent_id.as_ref().filter(|p| by_id.contains_key(p)) else {
+            continue;
+        };
+        if !firsts.contains_key(parent) && !lasts.contains_key(parent) {
+            parents.push(parent);
+        }
+        if node.before_sibling.is_none() {
+            firsts.entry(parent).or_default().push(node.id.clone());
+        }
+        if node.next_sibling.is_none() {
+            lasts.entry(parent).or_default().push(node.id.clone());
+        }
+    }
+
+    for parent in parents {
+        if let Some(ids) = firsts.remove(parent).filter(|ids| ids.len() > 1) {
+            errors.push(ValidationError::MultipleFirstChildren {
+                parent_id: parent.clone(),
+                node_ids: ids,
+            });
+        }
+        if let Some(ids) = lasts.remove(parent).filter(|ids| ids.len() > 1) {
+            errors.push(ValidationError::MultipleLastChildren {
+                parent_id: parent.clone(),
+                node_ids: ids,
+            });
+        }
+    }
+}
+
+fn check_root_ids(
+    nodes: &[Node],
+    by_id: &HashMap<&NodeId, &Node>,
+    in_parent_cycle: &HashSet<NodeId>,
+    errors: &mut Vec<ValidationError>,
+) {
+    for node in nodes {
+        // Unset root_id means the optimization is not configured for this node;
+        // hierarchy::audit_root_ids applies the same rule
+        let Some(actual) = node.root_id.as_ref() else {
+            continue;
+        };
+        if in_parent_cycle.contains(&node.id) {
+            continue;
+        }
+        if let Some(expected) = resolve_root(node, by_id) {
+            if expected != actual {
+                errors.push(ValidationError::RootMismatch {
+                    node_id: node.id.clone(),
+                    expected_root: expected.clone(),
+                    actual_root: Some(actual.clone()),
+                });
+            }
+        }
+    }
+}
+
+/// Find every cycle in the graph formed by a single pointer field
+///
+/// Each node has at most one outgoing edge, so every cycle is discovered exactly
+/// once. Cycles are returned in pointer order starting from the first node found.
+fn find_cycles<'a>(
+    nodes: &'a [Node],
+    pointer: impl Fn(&'a Node) -> Option<&'a NodeId>,
+    by_id: &HashMap<&NodeId, &'a Node>,
+) -> Vec<Vec<NodeId>> {
+    let mut finished: HashSet<&NodeId> = HashSet::new();
+    let mut cycles = Vec::new();
+
+    for start in nodes {
+        let mut path: Vec<&NodeId> = Vec::new();
+        let mut on_path: HashMap<&NodeId, usize> = HashMap::new();
+        let mut current = Some(start);
+
+        while let Some(node) = current {
+            if finished.contains(&node.id) {
+                break;
+            }
+            if let Some(&index) = on_path.get(&node.id) {
+                cycles.push(path[index..].iter().map(|id| (*id).clone()).collect());
+                break;
+            }
+            on_path.insert(&node.id, path.len());
+            path.push(&node.id);
+            current = pointer(node).and_then(|id| by_id.get(id).copied());
+        }
+
+        finished.extend(path);
+    }
+
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, parent: Option<&str>) -> Node {
+        let mut node = Node::with_id(id_of(id), "text".to_string(), serde_json::json!(id));
+        node.parent_id = parent.map(id_of);
+        node.root_id = Some(id_of("root"));
+        node
+    }
+
+    fn id_of(id: &str) -> NodeId {
+        NodeId::from_string(id.to_string())
+    }
+
+    /// root with children a → b → c
+    fn valid() -> Vec<Node> {
+        let mut nodes = vec![
+            node("root", None),
+            node("a", Some("root")),
+            node("b", Some("root")),
+            node("c", Some("root")),
+        ];
+        nodes[1].next_sibling = Some(id_of("b"));
+        nodes[2].before_sibling = Some(id_of("a"));
+        nodes[2].next_sibling = Some(id_of("c"));
+        nodes[3].before_sibling = Some(id_of("b"));
+        nodes
+    }
+
+    fn find<'n>(nodes: &'n mut [Node], id: &str) -> &'n mut Node {
+        nodes.iter_mut().find(|n| n.id.as_str() == id).unwrap()
+    }
+
+    /// The single error reported for a fixture
+    fn only_error(nodes: &[Node]) -> ValidationError {
+        let mut errors = validate_hierarchy(nodes);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        errors.remove(0)
+    }
+
+    #[test]
+    fn accepts_a_consistent_hierarchy() {
+        assert!(validate_hierarchy(&valid()).is_empty());
+        assert!(ensure_valid_hierarchy(&valid()).is_ok());
+    }
+
+    #[test]
+    fn reports_duplicate_ids() {
+        let mut nodes = valid();
+        nodes.push(node("b", Some("root")));
+        let errors = validate_hierarchy(&nodes);
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::DuplicateNodeId { node_id, count: 2 } if node_id.as_str() == "b"
+        )));
+        assert!(ensure_valid_hierarchy(&nodes).is_err());
+    }
+
+    #[test]
+    fn reports_dangling_references() {
+        let mut nodes = valid();
+        find(&mut nodes, "a").before_sibling = Some(id_of("missing"));
+        assert!(matches!(
+            only_error(&nodes),
+            ValidationError::DanglingReference { node_id, field, missing_id }
+                if node_id.as_str() == "a"
+                    && field == "before_sibling"
+                    && missing_id.as_str() == "missing"
+        ));
+    }
+
+    #[test]
+    fn reports_asymmetric_sibling_links() {
+        let mut nodes = valid();
+        find(&mut nodes, "c").before_sibling = Some(id_of("a"));
+        let errors = validate_hierarchy(&nodes);
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::AsymmetricSiblingLink { node_id, field, .. }
+                if node_id.as_str() == "b" && field == "next_sibling"
+        )));
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::AsymmetricSiblingLink { node_id, field, .. }
+                if node_id.as_str() == "c" && field == "before_sibling"
+        )));
+    }
+
+    #[test]
+    fn reports_sibling_cycles() {
+        let mut nodes = valid();
+        find(&mut nodes, "c").next_sibling = Some(id_of("a"));
+        find(&mut nodes, "a").before_sibling = Some(id_of("c"));
+        let errors = validate_hierarchy(&nodes);
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::SiblingCycle { node_ids } if node_ids.len() == 3
+        )));
+    }
+
+    #[test]
+    fn reports_parent_cycles() {
+        let mut nodes = vec![
+            node("root", None),
+            node("p", Some("q")),
+            node("q", Some("p")),
+        ];
+        for node in &mut nodes[1..] {
+            node.root_id = None;
+        }
+        assert!(matches!(
+            only_error(&nodes),
+            ValidationError::ParentCycle { node_ids } if node_ids.len() == 2
+        ));
+    }
+
+    #[test]
+    fn reports_siblings_with_different_parents() {
+        let mut nodes = valid();
+        nodes.push(node("other", Some("a")));
+        find(&mut nodes, "c").next_sibling = Some(id_of("other"));
+        find(&mut nodes, "other").before_sibling = Some(id_of("c"));
+        let errors = validate_hierarchy(&nodes);
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::SiblingParentMismatch { node_id, sibling_id, .. }
+                if node_id.as_str() == "c" && sibling_id.as_str() == "other"
+        )));
+    }
+
+    #[test]
+    fn reports_multiple_first_and_last_children() {
+        let mut nodes = valid();
+        nodes.push(node("loose", Some("root")));
+        let errors = validate_hierarchy(&nodes);
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::MultipleFirstChildren { parent_id, node_ids }
+                if parent_id.as_str() == "root" && node_ids == &[id_of("a"), id_of("loose")]
+        ));
+        assert!(matches!(
+            &errors[1],
+            ValidationError::MultipleLastChildren { node_ids, .. }
+                if node_ids == &[id_of("c"), id_of("loose")]
+        ));
+    }
+
+    #[test]
+    fn reports_root_mismatches_but_accepts_unset_roots() {
+        let mut nodes = valid();
+        nodes.push(node("other-root", None));
+        find(&mut nodes, "other-root").root_id = None;
+        find(&mut nodes, "b").root_id = Some(id_of("other-root"));
+        find(&mut nodes, "c").root_id = None;
+        assert!(matches!(
+            only_error(&nodes),
+            ValidationError::RootMismatch { node_id, expected_root, .. }
+                if node_id.as_str() == "b" && expected_root.as_str() == "root"
+        ));
+    }
+
+    #[test]
+    fn skips_tombstoned_nodes() {
+        let mut nodes = valid();
+        // b is deleted and unlinked, keeping its recorded pointers
+        find(&mut nodes, "a").next_sibling = Some(id_of("c"));
+        find(&mut nodes, "c").before_sibling = Some(id_of("a"));
+        find(&mut nodes, "b").deleted_at = Some(chrono::Utc::now());
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+}
@@ -106,6 +106,69 @@ pub mod compatibility {
     }
 }
 
+// ========================================
+// Hierarchy Utilities
+// ========================================
+
+pub mod hierarchy;
+pub mod integrity;
+pub mod ordering;
+pub mod tree;
+
+pub use hierarchy::{HierarchyChangeSet, RootIdReport, StaleRootId};
+pub use integrity::{ensure_valid_hierarchy, validate_hierarchy};
+pub use tree::NodeTree;
+
+// ========================================
+// Content, Schemas and Change Tracking
+// ========================================
+
+pub mod content;
+pub mod events;
+pub mod patch;
+pub mod schema;
+pub mod task;
+
+pub use content::{DateContent, LinkContent, NodeContent, TextContent};
+pub use events::NodeEvent;
+pub use patch::{JsonPatch, PatchOperation};
+pub use schema::{ContentSchema, FieldSchema, FieldType, SchemaRegistry};
+pub use task::{TaskContent, TaskPriority, TaskStatus};
+
+// ========================================
+// Dates, Calendars and Time Zones
+// ========================================
+
+pub mod calendar;
+pub mod locale;
+pub mod recurrence;
+pub mod relative_date;
+pub mod timestamps;
+pub mod timezone;
+
+pub use calendar::{CalendarLayout, CalendarPeriod};
+pub use locale::DateLocale;
+pub use recurrence::{ByDay, Frequency, RRule};
+pub use relative_date::{parse_relative_date, resolve_relative_date};
+pub use timezone::NodeTimezone;
+
+// ========================================
+// Import and Export
+// ========================================
+
+pub mod markdown;
+pub mod ndjson;
+pub mod opml;
+pub mod vault;
+
+pub use markdown::{
+    export_markdown, export_markdown_with, import_markdown, parse_markdown, DefaultMarkdownHooks,
+    MarkdownBlock, MarkdownHooks,
+};
+pub use ndjson::{ExportHeader, ExportRecord, NdjsonReader, NdjsonWriter, RelationshipRecord};
+pub use opml::{export_opml, import_opml, parse_opml, OpmlDocument};
+pub use vault::{import_vault, import_vault_files, read_vault, VaultFile, VaultImport};
+
 // NodeId - database-agnostic unique identifier
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct NodeId(pub String);
@@ -196,7 +259,7 @@ impl From<&str> for NodeId {
 /// ### Performance Benefits
 ///
 /// * **Before**: O(N × depth) multiple database queries to build hierarchy
-/// * **After**: O(1) single indexed query + O(M) memory operations  
+/// * **After**: O(1) single indexed query + O(M) memory operations (see [`NodeTree`])
 /// * **Expected improvement**: 10x-100x performance for hierarchical operations
 ///
 /// ### Usage Pattern
@@ -244,8 +307,10 @@ pub struct Node {
     pub r#type: String, // Required by LanceDB: "text", "date", "task", etc.
     pub content: serde_json::Value, // Flexible content
     pub metadata: Option<serde_json::Value>, // Optional system metadata
-    pub created_at: String, // ISO format timestamp
-    pub updated_at: String, // ISO format timestamp
+    #[serde(deserialize_with = "timestamps::deserialize")]
+    pub created_at: DateTime<Utc>,
+    #[serde(deserialize_with = "timestamps::deserialize")]
+    pub updated_at: DateTime<Utc>,
     // Hierarchical relationship
     pub parent_id: Option<NodeId>, // → Parent node (None = root)
     // Sibling navigation (bidirectional)
@@ -256,6 +321,30 @@ pub struct Node {
     /// Points to the hierarchy root node, enabling O(1) indexed queries instead of
     /// multiple O(N) scans. For root nodes, this points to the node itself.
     pub root_id: Option<NodeId>,
+    /// Fractional index for sibling ordering
+    ///
+    /// Lexicographically sortable alternative to the sibling linked list. When every
+    /// sibling in a group has a key, the keys take precedence over `before_sibling`
+    /// and `next_sibling`. See [`ordering`] for generating and converting keys.
+    #[serde(default)]
+    pub order_key: Option<String>,
+    /// Optimistic concurrency revision
+    ///
+    /// Starts at 1 and is incremented on every persisted change. Writers pass the
+    /// revision they read to [`Node::compare_and_set`] so that concurrent edits are
+    /// detected as [`DatabaseError::RevisionConflict`] instead of silently lost.
+    #[serde(default = "initial_revision")]
+    pub revision: u64,
+    /// Soft-delete tombstone: when the node was deleted (None = live)
+    #[serde(default, deserialize_with = "timestamps::deserialize_option")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Soft-delete tombstone: who deleted the node
+    #[serde(default)]
+    pub deleted_by: Option<String>,
+}
+
+fn initial_revision() -> u64 {
+    1
 }
 
 impl Node {
@@ -266,35 +355,53 @@ impl Node {
         #[cfg(not(feature = "performance-opts"))]
         let id = NodeId::new();
 
-        let now = chrono::Utc::now().to_rfc3339();
+        let now = Utc::now();
         Self {
             id,
             r#type,
             content,
             metadata: None,
-            created_at: now.clone(),
+            created_at: now,
             updated_at: now,
             parent_id: None,
             before_sibling: None,
             next_sibling: None,
             root_id: None,
+            order_key: None,
+            revision: initial_revision(),
+            deleted_at: None,
+            deleted_by: None,
         }
     }
 
+    /// Create a new Node with generated ID from a [`NodeType`]
+    pub fn of_kind(kind: NodeType, content: serde_json::Value) -> Self {
+        Self::new(kind.into(), content)
+    }
+
+    /// Create a Node with existing ID from a [`NodeType`]
+    pub fn with_id_of_kind(id: NodeId, kind: NodeType, content: serde_json::Value) -> Self {
+        Self::with_id(id, kind.into(), content)
+    }
+
     /// Create a Node with existing ID, type, and content
     pub fn with_id(id: NodeId, r#type: String, content: serde_json::Value) -> Self {
-        let now = chrono::Utc::now().to_rfc3339();
+        let now = Utc::now();
         Self {
             id,
             r#type,
             content,
             metadata: None,
-            created_at: now.clone(),
+            created_at: now,
             updated_at: now,
             parent_id: None,
             before_sibling: None,
             next_sibling: None,
             root_id: None,
+            order_key: None,
+            revision: initial_revision(),
+            deleted_at: None,
+            deleted_by: None,
         }
     }
 
@@ -306,7 +413,102 @@ impl Node {
 
     /// Update the node's timestamp
     pub fn touch(&mut self) {
-        self.updated_at = chrono::Utc::now().to_rfc3339();
+        self.updated_at = Utc::now();
+    }
+
+    /// Time elapsed since the node was created
+    pub fn age(&self, now: DateTime<Utc>) -> chrono::Duration {
+        now - self.created_at
+    }
+
+    /// Time elapsed since the node was last updated
+    pub fn time_since_update(&self, now: DateTime<Utc>) -> chrono::Duration {
+        now - self.updated_at
+    }
+
+    /// Check that the timestamps are in order
+    ///
+    /// `updated_at` (and `deleted_at`, when set) must not be earlier than
+    /// `created_at`.
+    pub fn validate_timestamps(&self) -> NodeSpaceResult<()> {
+        let checks = [
+            ("updated_at", Some(self.updated_at)),
+            ("deleted_at", self.deleted_at),
+        ];
+        for (field, value) in checks {
+            if let Some(value) = value.filter(|v| *v < self.created_at) {
+                return Err(ValidationError::BusinessRuleViolation {
+                    rule: format!("{} must not be earlier than created_at", field),
+                    context: serde_json::json!({
+                        "node_id": self.id,
+                        "created_at": self.created_at,
+                        field: value,
+                    }),
+                    resolution_steps: vec![format!(
+                        "Set {} to a time at or after created_at",
+                        field
+                    )],
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Increment the revision and update the timestamp
+    pub fn bump_revision(&mut self) {
+        self.revision += 1;
+        self.touch();
+    }
+
+    /// Check that the node is still at the revision a writer read
+    pub fn check_revision(&self, expected: u64) -> NodeSpaceResult<()> {
+        if self.revision == expected {
+            Ok(())
+        } else {
+            Err(
+                DatabaseError::revision_conflict("Node", self.id.as_str(), expected, self.revision)
+                    .into(),
+            )
+        }
+    }
+
+    /// Apply an update only if the node is still at `expected` revision
+    ///
+    /// On success the revision is incremented and the timestamp updated. If the
+    /// revision moved on, or the update itself fails, the node is left unchanged.
+    pub fn compare_and_set<F>(&mut self, expected: u64, update: F) -> NodeSpaceResult<()>
+    where
+        F: FnOnce(&mut Node) -> NodeSpaceResult<()>,
+    {
+        self.check_revision(expected)?;
+        let mut updated = self.clone();
+        update(&mut updated)?;
+        updated.id = self.id.clone();
+        updated.revision = self.revision;
+        updated.bump_revision();
+        *self = updated;
+        Ok(())
+    }
+
+    /// Replace this node with an edited copy if nobody else changed it meanwhile
+    ///
+    /// `proposed.revision` must be the revision the writer originally read. The
+    /// stored node takes the proposed fields and the next revision.
+    pub fn compare_and_replace(&mut self, proposed: Node) -> NodeSpaceResult<()> {
+        if proposed.id != self.id {
+            return Err(ValidationError::invalid_format(
+                "id",
+                self.id.as_str(),
+                proposed.id.as_str(),
+            )
+            .into());
+        }
+        let expected = proposed.revision;
+        self.compare_and_set(expected, |node| {
+            *node = proposed;
+            Ok(())
+        })
     }
 
     /// Set the next sibling pointer
@@ -327,6 +529,12 @@ impl Node {
         self
     }
 
+    /// Set the fractional ordering key
+    pub fn with_order_key(mut self, order_key: Option<String>) -> Self {
+        self.order_key = order_key;
+        self
+    }
+
     /// Update sibling pointers
     pub fn set_next_sibling(&mut self, next_sibling: Option<NodeId>) {
         self.next_sibling = next_sibling;
@@ -345,6 +553,50 @@ impl Node {
         self.touch();
     }
 
+    /// Update the fractional ordering key
+    pub fn set_order_key(&mut self, order_key: Option<String>) {
+        self.order_key = order_key;
+        self.touch();
+    }
+
+    /// Get the node type as a [`NodeType`]
+    ///
+    /// An empty `r#type` (never produced by this crate) is returned as
+    /// `NodeType::Custom("")`.
+    pub fn node_kind(&self) -> NodeType {
+        self.r#type
+            .parse()
+            .unwrap_or_else(|_| NodeType::Custom(self.r#type.clone()))
+    }
+
+    /// Set the node type from a [`NodeType`]
+    pub fn set_kind(&mut self, kind: NodeType) {
+        self.r#type = kind.into();
+        self.touch();
+    }
+
+    /// Check if this node is a soft-deleted tombstone
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Mark this single node as deleted
+    ///
+    /// Use [`hierarchy::tombstone_subtree`] to delete a node together with its
+    /// descendants and unlink it from its siblings.
+    pub fn mark_deleted(&mut self, actor: &str) {
+        self.deleted_at = Some(Utc::now());
+        self.deleted_by = Some(actor.to_string());
+        self.touch();
+    }
+
+    /// Clear the tombstone on this single node
+    pub fn clear_deleted(&mut self) {
+        self.deleted_at = None;
+        self.deleted_by = None;
+        self.touch();
+    }
+
     /// Check if this node has a parent
     pub fn has_parent(&self) -> bool {
         self.parent_id.is_some()
@@ -376,6 +628,8 @@ impl Node {
     }
 
     /// Create a new date node with proper schema-based structure
+    ///
+    /// The ID is deterministic (`date:YYYY-MM-DD`, see [`NodeId::for_date`]).
     pub fn new_date_node(date: chrono::NaiveDate) -> Self {
         let date_metadata = DateNodeMetadata::new(date);
         let content = serde_json::json!({
@@ -384,7 +638,7 @@ impl Node {
             "date_metadata": date_metadata
         });
 
-        Self::new("date".to_string(), content)
+        Self::with_id_of_kind(NodeId::for_date(date), NodeType::Date, content)
     }
 
     /// Create a date node with timezone context
@@ -396,7 +650,7 @@ impl Node {
             "date_metadata": date_metadata
         });
 
-        Self::new("date".to_string(), content)
+        Self::with_id_of_kind(NodeId::for_date(date), NodeType::Date, content)
     }
 
     /// Check if this node is a date node by examining its structure
@@ -426,6 +680,9 @@ impl Node {
     }
 
     /// Create a node with typed content (v3 preview feature)
+    ///
+    /// Prefer [`Node::from_typed`] with a [`NodeContent`] type, which derives the
+    /// node type from the content.
     #[cfg(feature = "v3-preview")]
     pub fn new_typed<T: serde::Serialize>(content: T, node_type: &str) -> NodeSpaceResult<Self> {
         let content_value =
@@ -436,30 +693,13 @@ impl Node {
                 fallback_formats: vec!["MessagePack".to_string()],
             })?;
 
-        let mut node = Self::new(content_value);
-        if let Some(metadata) = node.metadata.as_mut() {
-            if let Some(map) = metadata.as_object_mut() {
-                map.insert(
-                    "node_type".to_string(),
-                    serde_json::Value::String(node_type.to_string()),
-                );
-            }
-        } else {
-            node.metadata = Some(serde_json::json!({
-                "node_type": node_type
-            }));
-        }
-        Ok(node)
+        Ok(Self::new(node_type.to_string(), content_value))
     }
 
-    /// Get node type from metadata (v3 preview feature)
+    /// Get the node type (v3 preview feature)
     #[cfg(feature = "v3-preview")]
     pub fn node_type(&self) -> Option<String> {
-        self.metadata
-            .as_ref()
-            .and_then(|m| m.get("node_type"))
-            .and_then(|t| t.as_str())
-            .map(|s| s.to_string())
+        Some(self.r#type.clone())
     }
 
     /// Legacy method for backward compatibility
@@ -499,6 +739,16 @@ impl Node {
     pub fn is_hierarchy_root(&self) -> bool {
         matches!((&self.root_id, &self.parent_id), (Some(root_id), None) if root_id == &self.id)
     }
+
+    /// Mark this node as the root of its own hierarchy
+    ///
+    /// Points `root_id` at the node itself and updates the timestamp. Together with
+    /// `parent_id = None` this makes `is_hierarchy_root()` return true. Use
+    /// [`hierarchy::recompute_root_ids`] to propagate the new root to descendants.
+    pub fn mark_as_hierarchy_root(&mut self) {
+        self.root_id = Some(self.id.clone());
+        self.touch();
+    }
 }
 
 // Relationship reference for graph model
@@ -572,6 +822,16 @@ pub enum DatabaseError {
         can_retry: bool,
     },
 
+    #[error(
+        "Revision conflict on {entity_type} {id}: expected revision {expected}, found {actual}"
+    )]
+    RevisionConflict {
+        entity_type: String,
+        id: String,
+        expected: u64,
+        actual: u64,
+    },
+
     #[error("Index corruption detected: {index_name}")]
     IndexCorruption {
         index_name: String,
@@ -626,6 +886,64 @@ pub enum ValidationError {
         context: serde_json::Value,
         resolution_steps: Vec<String>,
     },
+
+    #[error("Dangling reference: {node_id}.{field} points to missing node {missing_id}")]
+    DanglingReference {
+        node_id: NodeId,
+        field: String,
+        missing_id: NodeId,
+    },
+
+    #[error("Duplicate node ID {node_id} appears {count} times")]
+    DuplicateNodeId { node_id: NodeId, count: usize },
+
+    #[error("Asymmetric sibling link: {node_id}.{field} is {sibling_id}, but the back-link is {back_link:?}")]
+    AsymmetricSiblingLink {
+        node_id: NodeId,
+        sibling_id: NodeId,
+        field: String,
+        back_link: Option<NodeId>,
+    },
+
+    #[error("Sibling cycle detected: {node_ids:?}")]
+    SiblingCycle { node_ids: Vec<NodeId> },
+
+    #[error("Parent cycle detected: {node_ids:?}")]
+    ParentCycle { node_ids: Vec<NodeId> },
+
+    #[error("Siblings {node_id} and {sibling_id} have different parents: {node_parent:?} vs {sibling_parent:?}")]
+    SiblingParentMismatch {
+        node_id: NodeId,
+        sibling_id: NodeId,
+        node_parent: Option<NodeId>,
+        sibling_parent: Option<NodeId>,
+    },
+
+    #[error("Multiple first children under {parent_id}: {node_ids:?}")]
+    MultipleFirstChildren {
+        parent_id: NodeId,
+        node_ids: Vec<NodeId>,
+    },
+
+    #[error("Multiple last children under {parent_id}: {node_ids:?}")]
+    MultipleLastChildren {
+        parent_id: NodeId,
+        node_ids: Vec<NodeId>,
+    },
+
+    #[error("Patch test failed at {path}: expected {expected}, found {actual}")]
+    PatchTestFailed {
+        path: String,
+        expected: serde_json::Value,
+        actual: serde_json::Value,
+    },
+
+    #[error("Root mismatch for {node_id}: expected {expected_root}, found {actual_root:?}")]
+    RootMismatch {
+        node_id: NodeId,
+        expected_root: NodeId,
+        actual_root: Option<NodeId>,
+    },
 }
 
 // Network errors with retry and recovery guidance
@@ -865,6 +1183,15 @@ impl DatabaseError {
             suggested_limit: Some(1000),
         }
     }
+
+    pub fn revision_conflict(entity_type: &str, id: &str, expected: u64, actual: u64) -> Self {
+        Self::RevisionConflict {
+            entity_type: entity_type.to_string(),
+            id: id.to_string(),
+            expected,
+            actual,
+        }
+    }
 }
 
 impl ValidationError {
@@ -1028,6 +1355,7 @@ impl NodeSpaceError {
         match self {
             Self::Database(DatabaseError::ConnectionFailed { .. }) => ErrorSeverity::Critical,
             Self::Database(DatabaseError::QueryTimeout { .. }) => ErrorSeverity::Warning,
+            Self::Database(DatabaseError::RevisionConflict { .. }) => ErrorSeverity::Warning,
             Self::Validation(_) => ErrorSeverity::Error,
             Self::Network(NetworkError::ConnectionTimeout { .. }) => ErrorSeverity::Warning,
             Self::Network(NetworkError::HttpError { status_code, .. }) if *status_code >= 500 => {
@@ -1141,7 +1469,11 @@ impl NodeMetadata {
 }
 
 // Node type classification enum
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+//
+// Serialized as the same lowercase string stored in `Node.r#type`, so the two
+// representations can't drift apart.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(try_from = "String", into = "String")]
 pub enum NodeType {
     #[default]
     Text,
@@ -1156,19 +1488,98 @@ pub enum NodeType {
     Custom(String),
 }
 
+impl NodeType {
+    /// Built-in node types, in declaration order
+    pub const BUILTIN: [NodeType; 9] = [
+        NodeType::Text,
+        NodeType::Image,
+        NodeType::Task,
+        NodeType::Document,
+        NodeType::Link,
+        NodeType::Entity,
+        NodeType::Date,
+        NodeType::Audio,
+        NodeType::Video,
+    ];
+
+    /// Create a node type from a name, resolving built-in names
+    ///
+    /// `NodeType::custom("task")` is `NodeType::Task`, so that every value
+    /// round-trips through its string form.
+    pub fn custom(name: &str) -> NodeSpaceResult<Self> {
+        Ok(name.parse()?)
+    }
+
+    /// The string stored in `Node.r#type` for this type
+    pub fn as_str(&self) -> &str {
+        match self {
+            NodeType::Text => "text",
+            NodeType::Image => "image",
+            NodeType::Task => "task",
+            NodeType::Document => "document",
+            NodeType::Link => "link",
+            NodeType::Entity => "entity",
+            NodeType::Date => "date",
+            NodeType::Audio => "audio",
+            NodeType::Video => "video",
+            NodeType::Custom(name) => name,
+        }
+    }
+
+    /// Check if this is a custom (non built-in) type
+    pub fn is_custom(&self) -> bool {
+        matches!(self, NodeType::Custom(_))
+    }
+}
+
 impl fmt::Display for NodeType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            NodeType::Text => write!(f, "text"),
-            NodeType::Image => write!(f, "image"),
-            NodeType::Task => write!(f, "task"),
-            NodeType::Document => write!(f, "document"),
-            NodeType::Link => write!(f, "link"),
-            NodeType::Entity => write!(f, "entity"),
-            NodeType::Date => write!(f, "date"),
-            NodeType::Audio => write!(f, "audio"),
-            NodeType::Video => write!(f, "video"),
-            NodeType::Custom(name) => write!(f, "{}", name),
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for NodeType {
+    type Err = ValidationError;
+
+    /// Parse a node type; built-in names match case-insensitively so legacy
+    /// `"Image"` values are accepted, anything else becomes `Custom`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(ValidationError::InvalidFormat {
+                field: "type".to_string(),
+                expected: "non-empty node type name".to_string(),
+                actual: s.to_string(),
+                examples: vec!["text".to_string(), "task".to_string(), "recipe".to_string()],
+            });
+        }
+        Ok(Self::BUILTIN
+            .into_iter()
+            .find(|builtin| builtin.as_str().eq_ignore_ascii_case(s))
+            .unwrap_or_else(|| NodeType::Custom(s.to_string())))
+    }
+}
+
+impl TryFrom<&str> for NodeType {
+    type Error = ValidationError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for NodeType {
+    type Error = ValidationError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<NodeType> for String {
+    fn from(node_type: NodeType) -> Self {
+        match node_type {
+            NodeType::Custom(name) => name,
+            builtin => builtin.as_str().to_string(),
         }
     }
 }
@@ -1201,24 +1612,12 @@ impl Default for DateNodeMetadata {
 impl DateNodeMetadata {
     /// Create DateNodeMetadata for a specific date
     pub fn new(date: chrono::NaiveDate) -> Self {
-        Self {
-            date: date.format("%Y-%m-%d").to_string(),
-            timezone: "UTC".to_string(),
-            display_format: date.format("%B %-d, %Y").to_string(),
-            created_by_navigation: true,
-            locale: Some("en-US".to_string()),
-        }
+        Self::with_timezone(date, "UTC")
     }
 
     /// Create DateNodeMetadata with timezone context
     pub fn with_timezone(date: chrono::NaiveDate, timezone: &str) -> Self {
-        Self {
-            date: date.format("%Y-%m-%d").to_string(),
-            timezone: timezone.to_string(),
-            display_format: date.format("%B %-d, %Y").to_string(),
-            created_by_navigation: true,
-            locale: Some("en-US".to_string()),
-        }
+        Self::localized(date, locale::DEFAULT_LOCALE, timezone)
     }
 
     /// Parse the stored date back to NaiveDate
@@ -1626,15 +2025,19 @@ impl ImageNode {
 
         Ok(Node {
             id: self.id.clone(),
-            r#type: "image".to_string(),
+            r#type: NodeType::Image.into(),
             content,
             metadata: None,
-            created_at: self.created_at.to_rfc3339(),
-            updated_at: self.updated_at.to_rfc3339(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
             parent_id: self.parent_id.clone(),
             before_sibling: self.before_sibling.clone(),
             next_sibling: self.next_sibling.clone(),
             root_id: self.root_id.clone(),
+            order_key: None,
+            revision: initial_revision(),
+            deleted_at: None,
+            deleted_by: None,
         })
     }
 
@@ -1667,6 +2070,7 @@ pub enum ContextStrategy {
     Adaptive,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for ContextStrategy {
     fn default() -> Self {
         Self::RuleBased
@@ -1819,3 +2223,193 @@ impl MultiLevelEmbeddings {
         count
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node() -> Node {
+        Node::with_id(
+            NodeId::from_string("n".to_string()),
+            "text".to_string(),
+            serde_json::json!("original"),
+        )
+    }
+
+    fn assert_conflict(result: NodeSpaceResult<()>, expected: u64, actual: u64) {
+        match result {
+            Err(NodeSpaceError::Database(DatabaseError::RevisionConflict {
+                entity_type,
+                id,
+                expected: e,
+                actual: a,
+            })) => {
+                assert_eq!(entity_type, "Node");
+                assert_eq!(id, "n");
+                assert_eq!((e, a), (expected, actual));
+            }
+            other => panic!("expected a revision conflict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn revision_conflict_names_both_revisions() {
+        let error = DatabaseError::revision_conflict("Node", "n", 2, 5);
+        assert_eq!(
+            error.to_string(),
+            "Revision conflict on Node n: expected revision 2, found 5"
+        );
+    }
+
+    #[test]
+    fn compare_and_set_applies_update_at_expected_revision() {
+        let mut node = node();
+        node.compare_and_set(1, |n| {
+            n.content = serde_json::json!("edited");
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(node.content, serde_json::json!("edited"));
+        assert_eq!(node.revision, 2);
+        assert!(node.check_revision(2).is_ok());
+    }
+
+    #[test]
+    fn compare_and_set_rejects_stale_revision_without_changes() {
+        let mut node = node();
+        node.bump_revision();
+        let before = serde_json::to_value(&node).unwrap();
+
+        let result = node.compare_and_set(1, |n| {
+            n.content = serde_json::json!("lost update");
+            Ok(())
+        });
+        assert_conflict(result, 1, 2);
+        assert_eq!(serde_json::to_value(&node).unwrap(), before);
+    }
+
+    #[test]
+    fn compare_and_set_keeps_node_when_update_fails() {
+        let mut node = node();
+        let before = serde_json::to_value(&node).unwrap();
+
+        let result = node.compare_and_set(1, |n| {
+            n.content = serde_json::json!("half done");
+            Err(ValidationError::required_field("content", "test").into())
+        });
+        assert!(result.is_err());
+        assert_eq!(serde_json::to_value(&node).unwrap(), before);
+    }
+
+    #[test]
+    fn compare_and_set_protects_id_and_revision() {
+        let mut node = node();
+        node.compare_and_set(1, |n| {
+            n.id = NodeId::from_string("other".to_string());
+            n.revision = 40;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(node.id.as_str(), "n");
+        assert_eq!(node.revision, 2);
+    }
+
+    #[test]
+    fn compare_and_replace_uses_proposed_revision() {
+        let mut stored = node();
+        let mut proposed = stored.clone();
+        proposed.content = serde_json::json!("edited");
+        stored.compare_and_replace(proposed.clone()).unwrap();
+        assert_eq!(stored.content, serde_json::json!("edited"));
+        assert_eq!(stored.revision, 2);
+
+        // Same proposal again was read at revision 1 and is now stale
+        let before = serde_json::to_value(&stored).unwrap();
+        proposed.content = serde_json::json!("second writer");
+        assert_conflict(stored.compare_and_replace(proposed), 1, 2);
+        assert_eq!(serde_json::to_value(&stored).unwrap(), before);
+    }
+
+    #[test]
+    fn compare_and_replace_rejects_other_node() {
+        let mut stored = node();
+        let other = Node::with_id(
+            NodeId::from_string("other".to_string()),
+            "text".to_string(),
+            serde_json::json!("x"),
+        );
+        assert!(matches!(
+            stored.compare_and_replace(other),
+            Err(NodeSpaceError::Validation(
+                ValidationError::InvalidFormat { .. }
+            ))
+        ));
+        assert_eq!(stored.revision, 1);
+    }
+
+    #[test]
+    fn node_type_serializes_as_type_string() {
+        for kind in NodeType::BUILTIN {
+            let json = serde_json::to_value(&kind).unwrap();
+            assert_eq!(json, serde_json::json!(kind.as_str()));
+            assert_eq!(serde_json::from_value::<NodeType>(json).unwrap(), kind);
+        }
+
+        let recipe = NodeType::custom("recipe").unwrap();
+        let json = serde_json::to_string(&recipe).unwrap();
+        assert_eq!(json, "\"recipe\"");
+        assert_eq!(serde_json::from_str::<NodeType>(&json).unwrap(), recipe);
+        assert!(serde_json::from_str::<NodeType>("\"\"").is_err());
+    }
+
+    #[test]
+    fn node_type_resolves_builtin_names() {
+        assert_eq!(NodeType::custom("task").unwrap(), NodeType::Task);
+        assert_eq!("video".parse::<NodeType>().unwrap(), NodeType::Video);
+        assert_eq!(NodeType::try_from("link").unwrap(), NodeType::Link);
+        assert_eq!(NodeType::default(), NodeType::Text);
+
+        let recipe = NodeType::custom("recipe").unwrap();
+        assert_eq!(recipe, NodeType::Custom("recipe".to_string()));
+        assert!(recipe.is_custom());
+        assert!(!NodeType::Date.is_custom());
+        assert_eq!(recipe.to_string(), "recipe");
+    }
+
+    #[test]
+    fn node_type_folds_case_of_builtin_names_only() {
+        assert_eq!(NodeType::custom("Image").unwrap(), NodeType::Image);
+        assert_eq!(NodeType::custom("DOCUMENT").unwrap(), NodeType::Document);
+        assert_eq!(String::from(NodeType::custom("Image").unwrap()), "image");
+        assert_eq!(
+            NodeType::custom("Recipe").unwrap(),
+            NodeType::Custom("Recipe".to_string())
+        );
+    }
+
+    #[test]
+    fn node_type_rejects_blank_names() {
+        for name in ["", "   "] {
+            assert!(matches!(
+                NodeType::custom(name),
+                Err(NodeSpaceError::Validation(
+                    ValidationError::InvalidFormat { .. }
+                ))
+            ));
+        }
+    }
+
+    #[test]
+    fn node_kind_matches_type_string() {
+        let mut node = Node::of_kind(NodeType::Task, serde_json::json!({}));
+        assert_eq!(node.r#type, "task");
+        assert_eq!(node.node_kind(), NodeType::Task);
+
+        node.set_kind(NodeType::custom("recipe").unwrap());
+        assert_eq!(node.r#type, "recipe");
+        node.r#type = "Entity".to_string();
+        assert_eq!(node.node_kind(), NodeType::Entity);
+        node.r#type = String::new();
+        assert_eq!(node.node_kind(), NodeType::Custom(String::new()));
+    }
+}
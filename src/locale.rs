@@ -0,0 +1,449 @@
+//! Locale tables for date display text
+//!
+//! Each [`DateLocale`] carries month and weekday names plus ordering patterns for
+//! a day, a month, and an ISO week. Patterns use the placeholders `{day}`,
+//! `{month}`, `{year}`, `{weekday}` and `{week}`. Lookups accept `de-DE`,
+//! `de_DE` or a bare language such as `de`. Constructing metadata falls back to
+//! [`DEFAULT_LOCALE`] for unsupported locales, while switching the locale of an
+//! existing date rejects them.
+
+use crate::calendar::CalendarPeriod;
+use crate::{DateNodeMetadata, Node, NodeSpaceResult, ValidationError};
+use chrono::{Datelike, NaiveDate};
+
+/// Locale used when none is given or the requested one is not supported
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Month and weekday names and date patterns for one locale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateLocale {
+    pub code: &'static str,
+    /// January first
+    pub month_names: [&'static str; 12],
+    /// Monday first
+    pub weekday_names: [&'static str; 7],
+    pub date_pattern: &'static str,
+    pub long_date_pattern: &'static str,
+    pub month_pattern: &'static str,
+    pub week_pattern: &'static str,
+}
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const ENGLISH_WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// Built-in locales
+pub const SUPPORTED_LOCALES: [DateLocale; 6] = [
+    DateLocale {
+        code: "en-US",
+        month_names: ENGLISH_MONTHS,
+        weekday_names: ENGLISH_WEEKDAYS,
+        date_pattern: "{month} {day}, {year}",
+        long_date_pattern: "{weekday}, {month} {day}, {year}",
+        month_pattern: "{month} {year}",
+        week_pattern: "Week {week}, {year}",
+    },
+    DateLocale {
+        code: "en-GB",
+        month_names: ENGLISH_MONTHS,
+        weekday_names: ENGLISH_WEEKDAYS,
+        date_pattern: "{day} {month} {year}",
+        long_date_pattern: "{weekday} {day} {month} {year}",
+        month_pattern: "{month} {year}",
+        week_pattern: "Week {week}, {year}",
+    },
+    DateLocale {
+        code: "de-DE",
+        month_names: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        weekday_names: [
+            "Montag",
+            "Dienstag",
+            "Mittwoch",
+            "Donnerstag",
+            "Freitag",
+            "Samstag",
+            "Sonntag",
+        ],
+        date_pattern: "{day}. {month} {year}",
+        long_date_pattern: "{weekday}, {day}. {month} {year}",
+        month_pattern: "{month} {year}",
+        week_pattern: "KW {week}/{year}",
+    },
+    DateLocale {
+        code: "fr-FR",
+        month_names: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        weekday_names: [
+            "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+        ],
+        date_pattern: "{day} {month} {year}",
+        long_date_pattern: "{weekday} {day} {month} {year}",
+        month_pattern: "{month} {year}",
+        week_pattern: "Semaine {week}, {year}",
+    },
+    DateLocale {
+        code: "es-ES",
+        month_names: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        weekday_names: [
+            "lunes",
+            "martes",
+            "miércoles",
+            "jueves",
+            "viernes",
+            "sábado",
+            "domingo",
+        ],
+        date_pattern: "{day} de {month} de {year}",
+        long_date_pattern: "{weekday}, {day} de {month} de {year}",
+        month_pattern: "{month} de {year}",
+        week_pattern: "Semana {week}, {year}",
+    },
+    DateLocale {
+        code: "ja-JP",
+        month_names: [
+            "1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月",
+        ],
+        weekday_names: [
+            "月曜日",
+            "火曜日",
+            "水曜日",
+            "木曜日",
+            "金曜日",
+            "土曜日",
+            "日曜日",
+        ],
+        date_pattern: "{year}年{month}{day}日",
+        long_date_pattern: "{year}年{month}{day}日 {weekday}",
+        month_pattern: "{year}年{month}",
+        week_pattern: "{year}年 第{week}週",
+    },
+];
+
+impl DateLocale {
+    /// Find a built-in locale by tag, falling back to the first locale with the
+    /// same language
+    pub fn find(tag: &str) -> Option<&'static DateLocale> {
+        let tag = tag.trim().replace('_', "-");
+        SUPPORTED_LOCALES
+            .iter()
+            .find(|l| l.code.eq_ignore_ascii_case(&tag))
+            .or_else(|| {
+                let language = tag.split('-').next()?;
+                SUPPORTED_LOCALES.iter().find(|l| {
+                    l.code
+                        .split('-')
+                        .next()
+                        .is_some_and(|lang| lang.eq_ignore_ascii_case(language))
+                })
+            })
+    }
+
+    /// Find a built-in locale by tag, falling back to [`DEFAULT_LOCALE`]
+    pub fn resolve(tag: &str) -> &'static DateLocale {
+        Self::find(tag).unwrap_or(&SUPPORTED_LOCALES[0])
+    }
+
+    /// Find a built-in locale by tag, failing for unsupported locales
+    pub fn require(tag: &str) -> NodeSpaceResult<&'static DateLocale> {
+        Self::find(tag).ok_or_else(|| {
+            ValidationError::InvalidFormat {
+                field: "locale".to_string(),
+                expected: "a supported locale".to_string(),
+                actual: tag.to_string(),
+                examples: SUPPORTED_LOCALES
+                    .iter()
+                    .map(|l| l.code.to_string())
+                    .collect(),
+            }
+            .into()
+        })
+    }
+
+    pub fn month_name(&self, month: u32) -> &'static str {
+        self.month_names[(month as usize).clamp(1, 12) - 1]
+    }
+
+    pub fn weekday_name(&self, weekday: chrono::Weekday) -> &'static str {
+        self.weekday_names[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Format a day, e.g. "June 30, 2025" or "30. Juni 2025"
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        self.render(self.date_pattern, date)
+    }
+
+    /// Format a day including its weekday
+    pub fn format_long_date(&self, date: NaiveDate) -> String {
+        self.render(self.long_date_pattern, date)
+    }
+
+    /// Format the month containing `date`, e.g. "June 2025"
+    pub fn format_month(&self, date: NaiveDate) -> String {
+        self.render(self.month_pattern, date)
+    }
+
+    /// Format the ISO week containing `date`, using its ISO week-year
+    pub fn format_week(&self, date: NaiveDate) -> String {
+        let week = date.iso_week();
+        self.week_pattern
+            .replace("{week}", &week.week().to_string())
+            .replace("{year}", &week.year().to_string())
+    }
+
+    /// Format the display text of a calendar node starting at `start`
+    pub fn format_period(&self, start: NaiveDate, period: CalendarPeriod) -> String {
+        match period {
+            CalendarPeriod::Year => start.year().to_string(),
+            CalendarPeriod::Month => self.format_month(start),
+            CalendarPeriod::Week => self.format_week(start),
+            CalendarPeriod::Day => self.format_date(start),
+        }
+    }
+
+    fn render(&self, pattern: &str, date: NaiveDate) -> String {
+        pattern
+            .replace("{weekday}", self.weekday_name(date.weekday()))
+            .replace("{day}", &date.day().to_string())
+            .replace("{month}", self.month_name(date.month()))
+            .replace("{year}", &date.year().to_string())
+    }
+}
+
+impl DateNodeMetadata {
+    /// Create DateNodeMetadata with display text in the given locale
+    ///
+    /// Unsupported locales fall back to [`DEFAULT_LOCALE`]; the resolved locale is
+    /// stored.
+    pub fn localized(date: NaiveDate, locale: &str, timezone: &str) -> Self {
+        let resolved = DateLocale::resolve(locale);
+        Self {
+            date: date.format("%Y-%m-%d").to_string(),
+            timezone: timezone.to_string(),
+            display_format: resolved.format_date(date),
+            created_by_navigation: true,
+            locale: Some(resolved.code.to_string()),
+        }
+    }
+
+    /// Switch to another supported locale and re-render `display_format`
+    pub fn set_locale(&mut self, locale: &str, period: CalendarPeriod) -> NodeSpaceResult<()> {
+        let resolved = DateLocale::require(locale)?;
+        self.locale = Some(resolved.code.to_string());
+        self.rerender_display_format(period)
+    }
+
+    /// Re-render `display_format` from the stored date and locale
+    ///
+    /// The metadata does not record which span it describes, so the caller passes
+    /// the period of the owning node (see [`Node::calendar_period`]).
+    pub fn rerender_display_format(&mut self, period: CalendarPeriod) -> NodeSpaceResult<()> {
+        let date = self.parse_date().map_err(|_| {
+            ValidationError::invalid_format("date_metadata.date", "YYYY-MM-DD", &self.date)
+        })?;
+        let locale = DateLocale::resolve(self.locale.as_deref().unwrap_or(DEFAULT_LOCALE));
+        self.display_format = locale.format_period(date, period);
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Re-render a date node's display text for another supported locale
+    ///
+    /// Updates `content.content` and `date_metadata.display_format` /
+    /// `date_metadata.locale` in place, using the year, month or week form for
+    /// calendar period nodes. Other content fields are left untouched.
+    pub fn localize_date_node(&mut self, locale: &str) -> NodeSpaceResult<()> {
+        let (Some(mut metadata), Some(period)) = (self.get_date_metadata(), self.calendar_period())
+        else {
+            return Err(ValidationError::invalid_format(
+                "type",
+                "date node",
+                self.content
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or(&self.r#type),
+            )
+            .into());
+        };
+        metadata.set_locale(locale, period)?;
+
+        self.content["content"] = metadata.display_format.clone().into();
+        self.content["date_metadata"]["display_format"] = metadata.display_format.into();
+        self.content["date_metadata"]["locale"] = metadata.locale.into();
+        self.touch();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeId;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn find_accepts_tags_underscores_and_languages() {
+        assert_eq!(DateLocale::find("de-DE").unwrap().code, "de-DE");
+        assert_eq!(DateLocale::find("de_de").unwrap().code, "de-DE");
+        assert_eq!(DateLocale::find(" fr ").unwrap().code, "fr-FR");
+        assert_eq!(DateLocale::find("en").unwrap().code, "en-US");
+        assert_eq!(DateLocale::find("en-AU").unwrap().code, "en-US");
+        assert_eq!(DateLocale::find("en-GB").unwrap().code, "en-GB");
+        assert!(DateLocale::find("pt-BR").is_none());
+        assert!(DateLocale::find("").is_none());
+    }
+
+    #[test]
+    fn resolve_falls_back_and_require_rejects() {
+        assert_eq!(DateLocale::resolve("pt-BR").code, DEFAULT_LOCALE);
+        assert_eq!(DateLocale::resolve("ja").code, "ja-JP");
+        assert_eq!(DateLocale::require("es").unwrap().code, "es-ES");
+        assert!(DateLocale::require("pt-BR").is_err());
+    }
+
+    #[test]
+    fn formats_follow_locale_patterns() {
+        let day = date(2025, 6, 30);
+        assert_eq!(
+            DateLocale::resolve("en-US").format_date(day),
+            "June 30, 2025"
+        );
+        assert_eq!(DateLocale::resolve("de").format_date(day), "30. Juni 2025");
+        assert_eq!(
+            DateLocale::resolve("es").format_long_date(day),
+            "lunes, 30 de junio de 2025"
+        );
+        assert_eq!(DateLocale::resolve("ja").format_month(day), "2025年6月");
+        // 29 Dec 2025 is in ISO week 1 of 2026
+        assert_eq!(
+            DateLocale::resolve("de").format_week(date(2025, 12, 29)),
+            "KW 1/2026"
+        );
+    }
+
+    #[test]
+    fn set_locale_renders_the_node_period() {
+        let mut metadata = DateNodeMetadata::new(date(2025, 3, 1));
+        metadata.set_locale("fr", CalendarPeriod::Day).unwrap();
+        assert_eq!(metadata.locale.as_deref(), Some("fr-FR"));
+        assert_eq!(metadata.display_format, "1 mars 2025");
+
+        metadata.set_locale("de-DE", CalendarPeriod::Month).unwrap();
+        assert_eq!(metadata.display_format, "März 2025");
+        metadata
+            .rerender_display_format(CalendarPeriod::Year)
+            .unwrap();
+        assert_eq!(metadata.display_format, "2025");
+
+        assert!(metadata.set_locale("pt-BR", CalendarPeriod::Day).is_err());
+        assert_eq!(metadata.locale.as_deref(), Some("de-DE"));
+
+        metadata.date = "March 1".to_string();
+        assert!(metadata
+            .rerender_display_format(CalendarPeriod::Day)
+            .is_err());
+    }
+
+    #[test]
+    fn localized_metadata_falls_back_to_default_locale() {
+        let metadata = DateNodeMetadata::localized(date(2025, 3, 1), "pt-BR", "UTC");
+        assert_eq!(metadata.locale.as_deref(), Some(DEFAULT_LOCALE));
+        assert_eq!(metadata.display_format, "March 1, 2025");
+    }
+
+    #[test]
+    fn localize_date_node_uses_calendar_period() {
+        let mut day = Node::new_date_node(date(2025, 3, 1));
+        day.localize_date_node("de").unwrap();
+        assert_eq!(day.content["content"], "1. März 2025");
+        assert_eq!(
+            day.get_date_metadata().unwrap().locale.as_deref(),
+            Some("de-DE")
+        );
+
+        let mut month = Node::new_calendar_node(date(2025, 3, 1), CalendarPeriod::Month);
+        month.localize_date_node("es").unwrap();
+        assert_eq!(month.content["content"], "marzo de 2025");
+        assert_eq!(
+            month.get_date_metadata().unwrap().display_format,
+            "marzo de 2025"
+        );
+        assert_eq!(month.calendar_period(), Some(CalendarPeriod::Month));
+
+        let mut year = Node::new_calendar_node(date(2025, 1, 1), CalendarPeriod::Year);
+        year.localize_date_node("ja").unwrap();
+        assert_eq!(year.content["content"], "2025");
+
+        assert!(day.localize_date_node("pt-BR").is_err());
+        assert_eq!(day.content["content"], "1. März 2025");
+
+        let mut text = Node::with_id(
+            NodeId::from_string("t".to_string()),
+            "text".to_string(),
+            serde_json::json!("2025-03-01"),
+        );
+        assert!(text.localize_date_node("de").is_err());
+    }
+}
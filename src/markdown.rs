@@ -0,0 +1,869 @@
+//! Markdown outline import and export
+//!
+//! Import turns a Markdown document into `text` nodes shaped like its outline:
+//!
+//! - a heading becomes a child of the closest preceding heading of a lower level
+//!   and keeps its `#` marker in the content (`"## Goals"`);
+//! - list items nest by indentation under the enclosing heading or item, with
+//!   the bullet or number dropped (`- [ ]` task markers are kept);
+//! - paragraphs, fenced code blocks and anything else become a text node under
+//!   the heading or list item they belong to.
+//!
+//! Setext headings (`===` / `---` underlines) are recognized, thematic breaks
+//! are dropped and inline formatting is kept as written. Every service should
+//! import through [`import_markdown`] so a document yields the same outline
+//! everywhere.
+//!
+//! Export renders a subtree as nested bullets in sibling order, using the same
+//! ordering as [`NodeTree`], so nodes with broken sibling links are still
+//! written out. [`MarkdownHooks`] decides how each node is rendered; the
+//! defaults turn date nodes and `#` text into headings, tasks into `- [ ]` /
+//! `- [x]`, images into `![](filename)` and links into `[title](url)`.
+
+use crate::hierarchy::{append_subtrees, HierarchyChangeSet};
+use crate::tree::NodeTree;
+use crate::{
+    DatabaseError, DateNodeMetadata, ImageNode, LinkContent, Node, NodeId, NodeSpaceResult,
+    NodeType, TaskContent, TaskStatus,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Parse a Markdown document into nodes hanging under `parent_id`
+///
+/// Nodes are returned in document order with `parent_id`, sibling links and
+/// `root_id` set. The first top-level node has no `before_sibling`, so use
+/// [`import_markdown`] to append the document after existing children.
+///
+/// ```
+/// use nodespace_core_types::{markdown::parse_markdown, NodeId};
+///
+/// let page = NodeId::from_string("page".to_string());
+/// let nodes = parse_markdown("# Plan\n\n- Ship\n  - Tag release\n", &page, &page);
+/// assert_eq!(nodes.len(), 3);
+/// assert_eq!(nodes[0].content, "# Plan");
+/// assert_eq!(nodes[2].parent_id.as_ref(), Some(&nodes[1].id));
+/// ```
+pub fn parse_markdown(markdown: &str, parent_id: &NodeId, root_id: &NodeId) -> Vec<Node> {
+    let blocks = OutlineParser::parse(markdown);
+    let mut nodes: Vec<Node> = blocks
+        .iter()
+        .map(|block| Node::of_kind(NodeType::Text, block.text.clone().into()))
+        .collect();
+
+    let mut last_child: HashMap<Option<usize>, usize> = HashMap::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let parent = block
+            .parent
+            .map_or_else(|| parent_id.clone(), |parent| nodes[parent].id.clone());
+        nodes[index].parent_id = Some(parent);
+        nodes[index].root_id = Some(root_id.clone());
+
+        if let Some(previous) = last_child.insert(block.parent, index) {
+            let previous_id = nodes[previous].id.clone();
+            nodes[previous].next_sibling = Some(nodes[index].id.clone());
+            nodes[index].before_sibling = Some(previous_id);
+        }
+    }
+    nodes
+}
+
+/// Import a Markdown document as the last children of `parent_id`
+///
+/// Returns the imported nodes plus the previous last child of the parent, whose
+/// `next_sibling` now points into the import.
+pub fn import_markdown(
+    nodes: &[Node],
+    markdown: &str,
+    parent_id: &NodeId,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    append_subtrees(
+        nodes,
+        parent_id,
+        parse_markdown(markdown, parent_id, parent_id),
+    )
+}
+
+/// How a node appears in exported Markdown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkdownBlock {
+    /// A bullet at the node's depth; further lines are indented under it
+    Bullet(String),
+    /// A heading (level 1 to 6); the node's children start a new bullet list
+    Heading { level: usize, text: String },
+    /// Leave the node out and render its children at its depth
+    Skip,
+}
+
+/// Rendering hooks for [`export_markdown_with`]
+///
+/// Each hook receives the node and its decoded content. Override the ones that
+/// should render differently; the rest keep the default behaviour. Nodes whose
+/// content does not decode as their type fall back to [`MarkdownHooks::text`].
+pub trait MarkdownHooks {
+    /// Date nodes, as a level 2 heading with the display text
+    fn date(&self, _node: &Node, metadata: &DateNodeMetadata) -> MarkdownBlock {
+        MarkdownBlock::Heading {
+            level: 2,
+            text: metadata.display_format.clone(),
+        }
+    }
+
+    /// Tasks, as `[ ] title` or `[x] title` bullets
+    fn task(&self, _node: &Node, task: &TaskContent) -> MarkdownBlock {
+        let mark = if task.status == TaskStatus::Done {
+            'x'
+        } else {
+            ' '
+        };
+        MarkdownBlock::Bullet(format!("[{}] {}", mark, task.title))
+    }
+
+    /// Images, as `![](filename)` or `![description](filename)`
+    fn image(&self, _node: &Node, image: &ImageNode) -> MarkdownBlock {
+        let alt = image.user_description.as_deref().unwrap_or_default();
+        MarkdownBlock::Bullet(format!("![{}]({})", alt, image.filename))
+    }
+
+    /// Links, as `[title](url)`, or `<url>` without a title
+    fn link(&self, _node: &Node, link: &LinkContent) -> MarkdownBlock {
+        MarkdownBlock::Bullet(match &link.title {
+            Some(title) => format!("[{}]({})", title, link.url),
+            None => format!("<{}>", link.url),
+        })
+    }
+
+    /// Any other node, using its text; `#` headings stay headings
+    fn text(&self, node: &Node) -> MarkdownBlock {
+        let text = node_text(node);
+        match atx_heading(&text) {
+            Some((level, title)) if !text.contains('\n') => MarkdownBlock::Heading {
+                level,
+                text: title.to_string(),
+            },
+            _ => MarkdownBlock::Bullet(text),
+        }
+    }
+}
+
+/// The default rendering
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultMarkdownHooks;
+
+impl MarkdownHooks for DefaultMarkdownHooks {}
+
+/// Render the subtree under `root_id` as Markdown with the default hooks
+///
+/// Deleted nodes are left out.
+pub fn export_markdown(nodes: &[Node], root_id: &NodeId) -> NodeSpaceResult<String> {
+    export_markdown_with(nodes, root_id, &DefaultMarkdownHooks)
+}
+
+/// Render the subtree under `root_id` as Markdown
+pub fn export_markdown_with(
+    nodes: &[Node],
+    root_id: &NodeId,
+    hooks: &dyn MarkdownHooks,
+) -> NodeSpaceResult<String> {
+    let tree = NodeTree::from_nodes(nodes.to_vec());
+    if !tree.contains(root_id) {
+        return Err(DatabaseError::not_found("Node", root_id.as_str()).into());
+    }
+
+    let mut lines = Vec::new();
+    let mut stack = vec![(root_id, 0)];
+    let mut visited = HashSet::new();
+    while let Some((id, depth)) = stack.pop() {
+        let Some(node) = tree.get(id).filter(|_| visited.insert(id)) else {
+            continue;
+        };
+        let child_depth = match render_node(hooks, node) {
+            MarkdownBlock::Bullet(text) => {
+                let indent = "  ".repeat(depth);
+                let mut text_lines = text.lines();
+                lines.push(format!(
+                    "{}- {}",
+                    indent,
+                    text_lines.next().unwrap_or_default()
+                ));
+                for line in text_lines {
+                    lines.push(if line.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{}  {}", indent, line)
+                    });
+                }
+                depth + 1
+            }
+            MarkdownBlock::Heading { level, text } => {
+                if lines.last().is_some_and(|line: &String| !line.is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push(format!("{} {}", "#".repeat(level.clamp(1, 6)), text));
+                lines.push(String::new());
+                0
+            }
+            MarkdownBlock::Skip => depth,
+        };
+        stack.extend(
+            tree.child_ids(id)
+                .iter()
+                .rev()
+                .map(|child| (child, child_depth)),
+        );
+    }
+
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    let mut markdown = lines.join("\n");
+    markdown.push('\n');
+    Ok(markdown)
+}
+
+fn render_node(hooks: &dyn MarkdownHooks, node: &Node) -> MarkdownBlock {
+    let typed = match node.node_kind() {
+        NodeType::Date => node
+            .get_date_metadata()
+            .map(|metadata| hooks.date(node, &metadata)),
+        NodeType::Task => node
+            .content_as::<TaskContent>()
+            .ok()
+            .map(|task| hooks.task(node, &task)),
+        NodeType::Image => ImageNode::from_node(node)
+            .ok()
+            .map(|image| hooks.image(node, &image)),
+        NodeType::Link => node
+            .content_as::<LinkContent>()
+            .ok()
+            .map(|link| hooks.link(node, &link)),
+        _ => None,
+    };
+    typed.unwrap_or_else(|| hooks.text(node))
+}
+
+/// Display text of a node: string content, or the `content` / `text` / `title`
+/// field of object content, or the JSON itself
+pub(crate) fn node_text(node: &Node) -> String {
+    if let Some(text) = node.content.as_str() {
+        return text.to_string();
+    }
+    ["content", "text", "title"]
+        .iter()
+        .find_map(|field| node.content.get(field).and_then(|v| v.as_str()))
+        .map_or_else(|| node.content.to_string(), str::to_string)
+}
+
+/// A block of text and the index of the block it nests under
+#[derive(Debug)]
+struct Block {
+    parent: Option<usize>,
+    text: String,
+}
+
+#[derive(Debug)]
+struct Fence {
+    marker: String,
+    parent: Option<usize>,
+    indent: usize,
+    lines: Vec<String>,
+    /// Indent of the list item the fence opened on (`- ```lang`), which
+    /// deeper items nest under once the fence closes
+    item: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct OutlineParser {
+    blocks: Vec<Block>,
+    /// Open headings as (level, block)
+    headings: Vec<(usize, usize)>,
+    /// Open list items as (indent, block)
+    items: Vec<(usize, usize)>,
+    paragraph: Option<Block>,
+    fence: Option<Fence>,
+    after_blank: bool,
+}
+
+impl OutlineParser {
+    fn parse(markdown: &str) -> Vec<Block> {
+        let mut parser = Self::default();
+        for line in markdown.lines() {
+            parser.line(line.trim_end());
+        }
+        parser.flush_paragraph();
+        if let Some(fence) = parser.fence.take() {
+            parser.close_fence(fence);
+        }
+        parser.blocks
+    }
+
+    fn line(&mut self, line: &str) {
+        if let Some(fence) = self.fence.as_mut() {
+            fence
+                .lines
+                .push(strip_indent(line, fence.indent).to_string());
+            if closes_fence(line.trim_start(), &fence.marker) {
+                let fence = self.fence.take().expect("fence is open");
+                self.close_fence(fence);
+            }
+            return;
+        }
+
+        if line.trim().is_empty() {
+            self.flush_paragraph();
+            self.after_blank = true;
+            return;
+        }
+        let after_blank = std::mem::replace(&mut self.after_blank, false);
+        let indent = indent_width(line);
+        let text = line.trim_start();
+
+        if let Some(marker) = fence_marker(text) {
+            self.flush_paragraph();
+            let parent = self.container(indent);
+            self.fence = Some(Fence {
+                marker: marker.to_string(),
+                parent,
+                indent,
+                lines: vec![text.to_string()],
+                item: None,
+            });
+            return;
+        }
+
+        if indent < 4 {
+            if let Some(level) = setext_level(text).filter(|_| self.paragraph.is_some()) {
+                let paragraph = self.paragraph.take().expect("paragraph is open");
+                self.heading(level, &paragraph.text.replace('\n', " "));
+                return;
+            }
+            if let Some((level, title)) = atx_heading(text) {
+                self.flush_paragraph();
+                self.heading(level, title);
+                return;
+            }
+            if is_thematic_break(text) {
+                self.flush_paragraph();
+                self.items.clear();
+                return;
+            }
+        }
+
+        if let Some(item) = list_item(text) {
+            self.flush_paragraph();
+            let parent = self.container(indent);
+            if let Some(marker) = fence_marker(item) {
+                self.fence = Some(Fence {
+                    marker: marker.to_string(),
+                    parent,
+                    indent: indent + text.len() - item.len(),
+                    lines: vec![item.to_string()],
+                    item: Some(indent),
+                });
+                return;
+            }
+            let index = self.push(parent, item.to_string());
+            self.items.push((indent, index));
+            return;
+        }
+
+        if let Some(paragraph) = self.paragraph.as_mut() {
+            paragraph.text.push('\n');
+            paragraph.text.push_str(text);
+            return;
+        }
+
+        // Lazy continuation of the list item on the previous line
+        let last_block = self.blocks.len().checked_sub(1);
+        if let Some(&(_, item)) = self.items.last().filter(|_| !after_blank) {
+            if last_block == Some(item) {
+                let block = &mut self.blocks[item];
+                block.text.push('\n');
+                block.text.push_str(text);
+                return;
+            }
+        }
+
+        let parent = self.container(indent);
+        self.paragraph = Some(Block {
+            parent,
+            text: text.to_string(),
+        });
+    }
+
+    fn heading(&mut self, level: usize, title: &str) {
+        self.items.clear();
+        while self.headings.last().is_some_and(|(open, _)| *open >= level) {
+            self.headings.pop();
+        }
+        let parent = self.headings.last().map(|(_, block)| *block);
+        let text = format!("{} {}", "#".repeat(level), title);
+        let index = self.push(parent, text.trim_end().to_string());
+        self.headings.push((level, index));
+    }
+
+    /// Block that content starting at `indent` nests under, closing list items
+    /// it is not indented past
+    fn container(&mut self, indent: usize) -> Option<usize> {
+        while self.items.last().is_some_and(|(open, _)| *open >= indent) {
+            self.items.pop();
+        }
+        self.items
+            .last()
+            .or(self.headings.last())
+            .map(|(_, block)| *block)
+    }
+
+    fn close_fence(&mut self, fence: Fence) {
+        let index = self.push(fence.parent, fence.lines.join("\n"));
+        if let Some(indent) = fence.item {
+            self.items.push((indent, index));
+        }
+    }
+
+    fn flush_paragraph(&mut self) {
+        if let Some(paragraph) = self.paragraph.take() {
+            self.push(paragraph.parent, paragraph.text);
+        }
+    }
+
+    fn push(&mut self, parent: Option<usize>, text: String) -> usize {
+        self.blocks.push(Block { parent, text });
+        self.blocks.len() - 1
+    }
+}
+
+/// Leading whitespace width, counting a tab as four columns
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Remove up to `width` columns of leading whitespace
+fn strip_indent(line: &str, width: usize) -> &str {
+    let mut removed = 0;
+    for (offset, c) in line.char_indices() {
+        if removed >= width || !c.is_whitespace() {
+            return &line[offset..];
+        }
+        removed += if c == '\t' { 4 } else { 1 };
+    }
+    ""
+}
+
+fn fence_marker(text: &str) -> Option<&str> {
+    let first = text.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = text.chars().take_while(|c| *c == first).count();
+    (length >= 3).then(|| &text[..length])
+}
+
+fn closes_fence(text: &str, marker: &str) -> bool {
+    fence_marker(text).is_some_and(|closing| {
+        closing.starts_with(&marker[..1])
+            && closing.len() >= marker.len()
+            && text[closing.len()..].trim().is_empty()
+    })
+}
+
+/// `# Title` through `###### Title`, without a closing `#` sequence
+pub(crate) fn atx_heading(text: &str) -> Option<(usize, &str)> {
+    let level = text.chars().take_while(|c| *c == '#').count();
+    let rest = &text[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let title = rest.trim();
+    let closed = title.trim_end_matches('#');
+    let title = if closed.is_empty() || closed.ends_with([' ', '\t']) {
+        closed.trim_end()
+    } else {
+        title
+    };
+    Some((level, title))
+}
+
+fn setext_level(text: &str) -> Option<usize> {
+    if text.chars().all(|c| c == '=') {
+        Some(1)
+    } else if text.len() >= 2 && text.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Three or more `-`, `*` or `_`, optionally separated by spaces
+fn is_thematic_break(text: &str) -> bool {
+    let marks: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && ['-', '*', '_'].iter().any(|m| marks.iter().all(|c| c == m))
+}
+
+/// Text of a bullet (`-`, `*`, `+`) or ordered (`1.`, `1)`) list item
+fn list_item(text: &str) -> Option<&str> {
+    let marker_length = if text.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+        if !(1..=9).contains(&digits) || !text[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        digits + 1
+    };
+    let rest = &text[marker_length..];
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then(|| rest.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::validate_hierarchy;
+
+    fn page() -> Node {
+        let mut page = Node::with_id(
+            NodeId::from_string("page".to_string()),
+            "text".to_string(),
+            serde_json::json!("Page"),
+        );
+        page.mark_as_hierarchy_root();
+        page
+    }
+
+    /// Each parsed node as (index of its parent, or `None` for the page, text)
+    fn outline(markdown: &str) -> Vec<(Option<usize>, String)> {
+        let page = page();
+        let nodes = parse_markdown(markdown, &page.id, &page.id);
+        nodes
+            .iter()
+            .map(|node| {
+                let parent = nodes
+                    .iter()
+                    .position(|n| Some(&n.id) == node.parent_id.as_ref());
+                (parent, node_text(node))
+            })
+            .collect()
+    }
+
+    fn entry(parent: Option<usize>, text: &str) -> (Option<usize>, String) {
+        (parent, text.to_string())
+    }
+
+    #[test]
+    fn nests_bullets_under_headings_and_items() {
+        let markdown = "\
+# Plan
+Intro paragraph
+continued
+
+## Goals
+- Ship
+  - Tag release
+  1. Announce
+- [ ] Write docs
+# Notes
+";
+        assert_eq!(
+            outline(markdown),
+            vec![
+                entry(None, "# Plan"),
+                entry(Some(0), "Intro paragraph\ncontinued"),
+                entry(Some(0), "## Goals"),
+                entry(Some(2), "Ship"),
+                entry(Some(3), "Tag release"),
+                entry(Some(3), "Announce"),
+                entry(Some(2), "[ ] Write docs"),
+                entry(None, "# Notes"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_code_fences_whole() {
+        let markdown = "\
+- Example
+  ```rust
+  - not a bullet
+
+  # not a heading
+  ```
+- After
+~~~
+unclosed
+";
+        assert_eq!(
+            outline(markdown),
+            vec![
+                entry(None, "Example"),
+                entry(Some(0), "```rust\n- not a bullet\n\n# not a heading\n```"),
+                entry(None, "After"),
+                entry(None, "~~~\nunclosed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn recognizes_setext_headings_and_drops_thematic_breaks() {
+        let markdown = "\
+Title
+=====
+Body
+
+Sub
+---
+- item
+***
+After the break
+";
+        assert_eq!(
+            outline(markdown),
+            vec![
+                entry(None, "# Title"),
+                entry(Some(0), "Body"),
+                entry(Some(0), "## Sub"),
+                entry(Some(2), "item"),
+                entry(Some(2), "After the break"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parsed_nodes_are_linked_in_document_order() {
+        let page = page();
+        let nodes = parse_markdown("- a\n  - b\n  - c\n- d\n", &page.id, &page.id);
+        let [a, b, c, d] = &nodes[..] else {
+            panic!("expected four nodes");
+        };
+        assert_eq!(a.next_sibling.as_ref(), Some(&d.id));
+        assert_eq!(d.before_sibling.as_ref(), Some(&a.id));
+        assert_eq!(b.next_sibling.as_ref(), Some(&c.id));
+        assert_eq!(b.before_sibling, None);
+        assert!(nodes.iter().all(|n| n.root_id.as_ref() == Some(&page.id)));
+    }
+
+    #[test]
+    fn import_appends_after_existing_children() {
+        let page = page();
+        let mut existing = Node::new("text".to_string(), serde_json::json!("existing"));
+        existing.parent_id = Some(page.id.clone());
+        existing.root_id = Some(page.id.clone());
+        let mut nodes = vec![page.clone(), existing.clone()];
+
+        let changes = import_markdown(&nodes, "- first\n  - nested\n- second\n", &page.id).unwrap();
+        assert_eq!(changes.updated.len(), 4);
+        changes.apply_to(&mut nodes);
+
+        let by_text = |text: &str| nodes.iter().find(|n| node_text(n) == text).unwrap();
+        let (first, nested, second) = (by_text("first"), by_text("nested"), by_text("second"));
+        assert_eq!(by_text("existing").next_sibling.as_ref(), Some(&first.id));
+        assert_eq!(first.before_sibling.as_ref(), Some(&existing.id));
+        assert_eq!(first.next_sibling.as_ref(), Some(&second.id));
+        assert_eq!(nested.parent_id.as_ref(), Some(&first.id));
+        assert!(nodes.iter().all(|n| n.root_id.as_ref() == Some(&page.id)));
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+
+    #[test]
+    fn import_of_a_long_document_stays_linked() {
+        let page = page();
+        let markdown: String = (0..2_000)
+            .map(|i| format!("- item {}\n  - detail {}\n", i, i))
+            .collect();
+        let mut nodes = vec![page.clone()];
+        import_markdown(&nodes, &markdown, &page.id)
+            .unwrap()
+            .apply_to(&mut nodes);
+
+        assert_eq!(nodes.len(), 4_001);
+        assert!(validate_hierarchy(&nodes).is_empty());
+        let tree = NodeTree::from_nodes(nodes.clone());
+        let items = tree.child_ids(&page.id);
+        assert_eq!(items.len(), 2_000);
+        assert_eq!(node_text(tree.get(&items[1_999]).unwrap()), "item 1999");
+    }
+
+    /// Link `children` in order under `parent`
+    fn children_of(parent: &Node, children: Vec<Node>) -> Vec<Node> {
+        let mut children = children;
+        for index in 0..children.len() {
+            children[index].parent_id = Some(parent.id.clone());
+            children[index].root_id = parent.root_id.clone();
+            if index > 0 {
+                children[index].before_sibling = Some(children[index - 1].id.clone());
+                children[index - 1].next_sibling = Some(children[index].id.clone());
+            }
+        }
+        children
+    }
+
+    #[test]
+    fn exports_typed_nodes_with_default_hooks() {
+        let day = chrono::NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        let mut date = Node::new_date_node(day);
+        date.mark_as_hierarchy_root();
+        let display = date.get_date_metadata().unwrap().display_format;
+
+        let mut done = TaskContent::new("Ship");
+        done.status = TaskStatus::Done;
+        let mut docs = LinkContent::new("https://example.com/docs");
+        docs.title = Some("Docs".to_string());
+        let mut deleted = Node::new("text".to_string(), serde_json::json!("gone"));
+        deleted.deleted_at = Some(chrono::Utc::now());
+        let image = ImageNode::new(
+            Vec::new(),
+            "chart.png".to_string(),
+            "image/png".to_string(),
+            (1, 1),
+        );
+
+        let mut nodes = children_of(
+            &date,
+            vec![
+                Node::of_kind(NodeType::Task, serde_json::to_value(&done).unwrap()),
+                Node::of_kind(NodeType::Task, serde_json::json!({ "title": "Review" })),
+                deleted,
+                Node::of_kind(NodeType::Link, serde_json::to_value(&docs).unwrap()),
+                Node::of_kind(
+                    NodeType::Link,
+                    serde_json::to_value(LinkContent::new("https://example.com")).unwrap(),
+                ),
+                image.to_node().unwrap(),
+                Node::new(
+                    "text".to_string(),
+                    serde_json::json!("line one\n\nline two"),
+                ),
+            ],
+        );
+        nodes.push(date.clone());
+
+        assert_eq!(
+            export_markdown(&nodes, &date.id).unwrap(),
+            format!(
+                "## {}\n\n\
+                 - [x] Ship\n\
+                 - [ ] Review\n\
+                 - [Docs](https://example.com/docs)\n\
+                 - <https://example.com>\n\
+                 - ![](chart.png)\n\
+                 - line one\n\
+                 \n  line two\n",
+                display
+            )
+        );
+    }
+
+    #[test]
+    fn export_uses_custom_hooks() {
+        struct Flat;
+        impl MarkdownHooks for Flat {
+            fn text(&self, node: &Node) -> MarkdownBlock {
+                match node_text(node).as_str() {
+                    "group" => MarkdownBlock::Skip,
+                    text => MarkdownBlock::Bullet(text.to_uppercase()),
+                }
+            }
+        }
+
+        let page = page();
+        let group = Node::new("text".to_string(), serde_json::json!("group"));
+        let mut nodes = children_of(&page, vec![group.clone()]);
+        nodes.extend(children_of(
+            &nodes[0].clone(),
+            vec![
+                Node::new("text".to_string(), serde_json::json!("a")),
+                Node::new("text".to_string(), serde_json::json!("b")),
+            ],
+        ));
+        nodes.push(page.clone());
+
+        assert_eq!(
+            export_markdown_with(&nodes, &page.id, &Flat).unwrap(),
+            "- PAGE\n  - A\n  - B\n"
+        );
+        assert!(export_markdown(&nodes, &NodeId::from_string("missing".to_string())).is_err());
+    }
+
+    #[test]
+    fn import_export_round_trip() {
+        let body = "\
+## Goals
+
+- Ship
+  - Tag release
+  - ```sh
+    cargo publish
+    ```
+- [ ] Write docs
+
+## Notes
+
+- Paragraph
+  continued
+";
+        let mut page = page();
+        page.content = serde_json::json!("# Page");
+        let mut nodes = vec![page.clone()];
+        import_markdown(&nodes, body, &page.id)
+            .unwrap()
+            .apply_to(&mut nodes);
+
+        let exported = export_markdown(&nodes, &page.id).unwrap();
+        assert_eq!(exported, format!("# Page\n\n{}", body));
+
+        let reexported = {
+            let mut again = vec![page.clone()];
+            let body = exported.strip_prefix("# Page\n\n").unwrap();
+            import_markdown(&again, body, &page.id)
+                .unwrap()
+                .apply_to(&mut again);
+            export_markdown(&again, &page.id).unwrap()
+        };
+        assert_eq!(reexported, exported);
+    }
+
+    fn text_nodes(texts: &[&str]) -> Vec<Node> {
+        texts
+            .iter()
+            .map(|text| {
+                Node::with_id(
+                    NodeId::from_string(text.to_string()),
+                    "text".to_string(),
+                    serde_json::json!(text),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn export_survives_broken_sibling_chains() {
+        let page = page();
+        let mut nodes = children_of(&page, text_nodes(&["a", "b", "c", "d"]));
+        let missing = NodeId::from_string("missing".to_string());
+        nodes[1].next_sibling = Some(missing.clone());
+        nodes[2].before_sibling = Some(missing);
+        nodes.extend(children_of(&nodes[2].clone(), text_nodes(&["c1"])));
+        nodes.push(page.clone());
+
+        assert_eq!(
+            export_markdown(&nodes, &page.id).unwrap(),
+            "- Page\n  - a\n  - b\n  - c\n    - c1\n  - d\n"
+        );
+    }
+
+    #[test]
+    fn export_survives_cyclic_sibling_chains() {
+        let page = page();
+        let mut nodes = children_of(&page, text_nodes(&["x", "y"]));
+        nodes[0].before_sibling = Some(nodes[1].id.clone());
+        nodes[1].next_sibling = Some(nodes[0].id.clone());
+        nodes.extend(children_of(&nodes[1].clone(), text_nodes(&["y1"])));
+        // A node listing itself as its next sibling
+        nodes.extend(children_of(&page, text_nodes(&["z"])));
+        nodes[3].next_sibling = Some(nodes[3].id.clone());
+        nodes.push(page.clone());
+
+        assert_eq!(
+            export_markdown(&nodes, &page.id).unwrap(),
+            "- Page\n  - z\n  - x\n  - y\n    - y1\n"
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! Versioned JSON Lines workspace dumps
+//!
+//! A dump is newline-delimited JSON: one [`ExportHeader`] record followed by
+//! one record per [`Node`], [`ImageNode`] or relationship, each tagged with a
+//! `record` field:
+//!
+//! ```text
+//! {"record":"header","format":"nodespace-ndjson","format_version":1,"core_types_version":"3.0.0",...}
+//! {"record":"node","id":"...","type":"text","content":"Hello",...}
+//! {"record":"relationship","source_id":"...","target_id":"...","relationship_type":"mentions",...}
+//! ```
+//!
+//! [`NdjsonWriter`] writes records as they are produced and [`NdjsonReader`]
+//! yields them one line at a time, so neither side holds the workspace in
+//! memory. The reader accepts dumps written with the same [`CORE_TYPES_VERSION`]
+//! whatever features either build enabled, and otherwise rejects those that are
+//! not compatible according to [`crate::compatibility::is_compatible_with`].
+
+use crate::{
+    compatibility, features, ImageNode, Node, NodeId, NodeSpaceError, NodeSpaceResult,
+    ProcessingError, RelationshipRef, ServiceError, ValidationError, CORE_TYPES_VERSION,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+/// Value of the header `format` field
+pub const NDJSON_FORMAT: &str = "nodespace-ndjson";
+
+/// Current version of the record layout
+pub const NDJSON_FORMAT_VERSION: u32 = 1;
+
+/// First record of every dump
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportHeader {
+    pub format: String,
+    pub format_version: u32,
+    /// [`CORE_TYPES_VERSION`] of the exporting build
+    pub core_types_version: String,
+    /// [`features::active_features`] of the exporting build
+    pub features: Vec<String>,
+    pub exported_at: DateTime<Utc>,
+}
+
+impl ExportHeader {
+    /// Header describing this build, stamped with the current time
+    pub fn current() -> Self {
+        Self {
+            format: NDJSON_FORMAT.to_string(),
+            format_version: NDJSON_FORMAT_VERSION,
+            core_types_version: CORE_TYPES_VERSION.to_string(),
+            features: features::active_features()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            exported_at: Utc::now(),
+        }
+    }
+
+    /// Check that this build can read the dump the header describes
+    pub fn validate(&self) -> NodeSpaceResult<()> {
+        if self.format != NDJSON_FORMAT {
+            return Err(ValidationError::InvalidFormat {
+                field: "header.format".to_string(),
+                expected: NDJSON_FORMAT.to_string(),
+                actual: self.format.clone(),
+                examples: vec![NDJSON_FORMAT.to_string()],
+            }
+            .into());
+        }
+        if self.format_version == 0 || self.format_version > NDJSON_FORMAT_VERSION {
+            return Err(ValidationError::out_of_range(
+                "header.format_version",
+                &self.format_version.to_string(),
+                "1",
+                &NDJSON_FORMAT_VERSION.to_string(),
+            )
+            .into());
+        }
+        if self.core_types_version != CORE_TYPES_VERSION
+            && !compatibility::is_compatible_with(&self.core_types_version)
+        {
+            return Err(ServiceError::VersionMismatch {
+                service: "nodespace-core-types".to_string(),
+                expected: CORE_TYPES_VERSION.to_string(),
+                actual: self.core_types_version.clone(),
+                compatibility_matrix: std::iter::once(CORE_TYPES_VERSION)
+                    .chain(
+                        compatibility::compatibility_matrix()
+                            .into_iter()
+                            .filter(|(feature, _)| features::active_features().contains(feature))
+                            .flat_map(|(_, versions)| versions),
+                    )
+                    .map(str::to_string)
+                    .collect(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// A relationship from `source_id`, as stored in a dump
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationshipRecord {
+    pub source_id: NodeId,
+    #[serde(flatten)]
+    pub relationship: RelationshipRef,
+}
+
+/// One line of a dump
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Header(ExportHeader),
+    Node(Node),
+    Image(Box<ImageNode>),
+    Relationship(RelationshipRecord),
+}
+
+/// Borrowed form of [`ExportRecord`] so writing does not clone
+#[derive(Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum RecordRef<'a> {
+    Header(&'a ExportHeader),
+    Node(&'a Node),
+    Image(&'a ImageNode),
+    Relationship(RelationshipRecordRef<'a>),
+}
+
+#[derive(Serialize)]
+struct RelationshipRecordRef<'a> {
+    source_id: &'a NodeId,
+    #[serde(flatten)]
+    relationship: &'a RelationshipRef,
+}
+
+/// Streaming dump writer
+///
+/// The header is written on construction. Call [`NdjsonWriter::finish`] to
+/// flush and get the underlying writer back.
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+    records: u64,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    /// Start a dump with [`ExportHeader::current`]
+    pub fn new(writer: W) -> NodeSpaceResult<Self> {
+        Self::with_header(writer, &ExportHeader::current())
+    }
+
+    /// Start a dump with a given header
+    pub fn with_header(writer: W, header: &ExportHeader) -> NodeSpaceResult<Self> {
+        let mut dump = Self { writer, records: 0 };
+        dump.write(&RecordRef::Header(header))?;
+        Ok(dump)
+    }
+
+    pub fn write_node(&mut self, node: &Node) -> NodeSpaceResult<()> {
+        self.write(&RecordRef::Node(node))
+    }
+
+    pub fn write_image(&mut self, image: &ImageNode) -> NodeSpaceResult<()> {
+        self.write(&RecordRef::Image(image))
+    }
+
+    pub fn write_relationship(
+        &mut self,
+        source_id: &NodeId,
+        relationship: &RelationshipRef,
+    ) -> NodeSpaceResult<()> {
+        self.write(&RecordRef::Relationship(RelationshipRecordRef {
+            source_id,
+            relationship,
+        }))
+    }
+
+    /// Number of records written after the header
+    pub fn records_written(&self) -> u64 {
+        self.records.saturating_sub(1)
+    }
+
+    /// Flush and return the underlying writer
+    pub fn finish(mut self) -> NodeSpaceResult<W> {
+        self.writer.flush().map_err(io_error)?;
+        Ok(self.writer)
+    }
+
+    fn write(&mut self, record: &RecordRef) -> NodeSpaceResult<()> {
+        serde_json::to_writer(&mut self.writer, record).map_err(|e| {
+            NodeSpaceError::from(ProcessingError::SerializationFailed {
+                format: "NDJSON".to_string(),
+                reason: e.to_string(),
+                data_type: "ExportRecord".to_string(),
+                fallback_formats: vec!["JSON".to_string()],
+            })
+        })?;
+        self.writer.write_all(b"\n").map_err(io_error)?;
+        self.records += 1;
+        Ok(())
+    }
+}
+
+/// Streaming dump reader
+///
+/// Reads and validates the header on construction, then yields the remaining
+/// records in order. Blank lines are skipped; a malformed line or a second
+/// header yields an error for that line and reading can continue.
+pub struct NdjsonReader<R: BufRead> {
+    reader: R,
+    header: ExportHeader,
+    line_number: usize,
+    buffer: String,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    /// Read the header and check it with [`ExportHeader::validate`]
+    pub fn new(reader: R) -> NodeSpaceResult<Self> {
+        let mut dump = Self {
+            reader,
+            header: ExportHeader::current(),
+            line_number: 0,
+            buffer: String::new(),
+        };
+        dump.header = match dump.next_record() {
+            Some(Ok(ExportRecord::Header(header))) => header,
+            Some(Err(error)) => return Err(error),
+            Some(Ok(_)) | None => {
+                return Err(ValidationError::RequiredFieldMissing {
+                    field: "header".to_string(),
+                    context: "NDJSON dump".to_string(),
+                    suggestion: Some(
+                        "The first record must have \"record\": \"header\"".to_string(),
+                    ),
+                }
+                .into())
+            }
+        };
+        dump.header.validate()?;
+        Ok(dump)
+    }
+
+    /// Header of the dump being read
+    pub fn header(&self) -> &ExportHeader {
+        &self.header
+    }
+
+    fn next_record(&mut self) -> Option<NodeSpaceResult<ExportRecord>> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(io_error(e))),
+            }
+            self.line_number += 1;
+            let line = self.buffer.trim();
+            if line.is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_str(line).map_err(|e| {
+                ProcessingError::SerializationFailed {
+                    format: "NDJSON".to_string(),
+                    reason: format!("line {}: {}", self.line_number, e),
+                    data_type: "ExportRecord".to_string(),
+                    fallback_formats: vec!["JSON".to_string()],
+                }
+                .into()
+            }));
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = NodeSpaceResult<ExportRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_record()? {
+            Ok(ExportRecord::Header(_)) => Some(Err(ValidationError::BusinessRuleViolation {
+                rule: "A dump has exactly one header".to_string(),
+                context: serde_json::json!({ "line": self.line_number }),
+                resolution_steps: vec!["Split concatenated dumps before reading".to_string()],
+            }
+            .into())),
+            other => Some(other),
+        }
+    }
+}
+
+fn io_error(error: std::io::Error) -> NodeSpaceError {
+    NodeSpaceError::IoError {
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read(dump: &str) -> NodeSpaceResult<NdjsonReader<Cursor<&str>>> {
+        NdjsonReader::new(Cursor::new(dump))
+    }
+
+    fn header_line(header: &ExportHeader) -> String {
+        serde_json::to_string(&ExportRecord::Header(header.clone())).unwrap()
+    }
+
+    #[test]
+    fn writer_output_reads_back() {
+        let mut node = Node::new("text".to_string(), serde_json::json!("Hello"));
+        node.order_key = Some("V".to_string());
+        let image = ImageNode::new(
+            vec![1, 2, 3],
+            "chart.png".to_string(),
+            "image/png".to_string(),
+            (2, 1),
+        );
+        let mut relationship = RelationshipRef::new(image.id.clone(), "mentions".to_string());
+        relationship.properties = serde_json::json!({ "weight": 2 });
+
+        let mut writer = NdjsonWriter::new(Vec::new()).unwrap();
+        writer.write_node(&node).unwrap();
+        writer.write_image(&image).unwrap();
+        writer.write_relationship(&node.id, &relationship).unwrap();
+        assert_eq!(writer.records_written(), 3);
+        let dump = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(dump.lines().count(), 4);
+
+        let reader = read(&dump).unwrap();
+        assert_eq!(reader.header().core_types_version, CORE_TYPES_VERSION);
+        let records: Vec<ExportRecord> = reader.map(Result::unwrap).collect();
+        let [ExportRecord::Node(read_node), ExportRecord::Image(read_image), ExportRecord::Relationship(read_relationship)] =
+            &records[..]
+        else {
+            panic!("unexpected records {:?}", records);
+        };
+        assert_eq!(
+            serde_json::to_value(read_node).unwrap(),
+            serde_json::to_value(&node).unwrap()
+        );
+        assert_eq!(**read_image, image);
+        assert_eq!(read_relationship.source_id, node.id);
+        assert_eq!(read_relationship.relationship.target_id, image.id);
+        assert_eq!(read_relationship.relationship.relationship_type, "mentions");
+        assert_eq!(
+            read_relationship.relationship.properties,
+            relationship.properties
+        );
+    }
+
+    #[test]
+    fn accepts_own_version_regardless_of_features() {
+        let mut header = ExportHeader::current();
+        header.features = vec!["v3-preview".to_string(), "experimental".to_string()];
+        assert!(header.validate().is_ok());
+        assert!(read(&header_line(&header)).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn rejects_incompatible_headers() {
+        let mut wrong_format = ExportHeader::current();
+        wrong_format.format = "other".to_string();
+        let mut future = ExportHeader::current();
+        future.format_version = NDJSON_FORMAT_VERSION + 1;
+        let mut unknown_version = ExportHeader::current();
+        unknown_version.core_types_version = "9.0.0".to_string();
+
+        for header in [wrong_format, future, unknown_version] {
+            assert!(read(&header_line(&header)).is_err(), "{:?}", header);
+        }
+        assert!(matches!(
+            ExportHeader {
+                core_types_version: "9.0.0".to_string(),
+                ..ExportHeader::current()
+            }
+            .validate(),
+            Err(NodeSpaceError::Service(
+                ServiceError::VersionMismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn requires_a_leading_header() {
+        assert!(read("").is_err());
+        let node = Node::new("text".to_string(), serde_json::json!("Hello"));
+        let line = serde_json::to_string(&ExportRecord::Node(node)).unwrap();
+        assert!(matches!(
+            read(&line),
+            Err(NodeSpaceError::Validation(
+                ValidationError::RequiredFieldMissing { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn reports_bad_lines_and_keeps_reading() {
+        let header = header_line(&ExportHeader::current());
+        let node = Node::new("text".to_string(), serde_json::json!("Hello"));
+        let node_line = serde_json::to_string(&ExportRecord::Node(node)).unwrap();
+        let dump = format!("{header}\n\n{{not json\n{header}\n{node_line}\n");
+
+        let results: Vec<_> = read(&dump).unwrap().collect();
+        assert_eq!(results.len(), 3);
+        match &results[0] {
+            Err(NodeSpaceError::Processing(ProcessingError::SerializationFailed {
+                reason,
+                ..
+            })) => assert!(reason.starts_with("line 3:"), "{}", reason),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(matches!(
+            results[1],
+            Err(NodeSpaceError::Validation(
+                ValidationError::BusinessRuleViolation { .. }
+            ))
+        ));
+        assert!(matches!(results[2], Ok(ExportRecord::Node(_))));
+    }
+}
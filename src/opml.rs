@@ -0,0 +1,762 @@
+//! OPML import and export
+//!
+//! Each `<outline>` element maps to one node: the `text` attribute becomes the
+//! text content and every other attribute is kept in `metadata.opml`, so an
+//! imported document exports back with the same attributes. A `created`
+//! attribute sets `created_at` instead when it parses as a timestamp; one that
+//! does not is kept and written back as it was.
+//!
+//! Exporting writes the children of a root node as the `<body>` outlines of an
+//! OPML 2.0 document titled with the root's text, with `created` in RFC 3339.
+//! The node ID, `updated_at`, `order_key` and revision, node types other than
+//! `text`, non-string content and metadata outside `metadata.opml` are carried
+//! in `nodespace:`-prefixed attributes, so the nodes survive a round trip as
+//! well. Sibling links, `parent_id` and `root_id` are rebuilt from the outline
+//! structure on import. Head elements other than `<title>` are not imported.
+//!
+//! The reader is a small XML parser covering what OPML uses: elements,
+//! attributes, text, CDATA, comments, processing instructions, a DOCTYPE and
+//! the predefined and numeric character references. Documents nested deeper
+//! than [`MAX_DEPTH`] elements are rejected.
+
+use crate::hierarchy::{append_subtrees, HierarchyChangeSet};
+use crate::markdown::node_text;
+use crate::timestamps::parse_timestamp;
+use crate::tree::NodeTree;
+use crate::{
+    DatabaseError, Node, NodeId, NodeSpaceResult, NodeType, ProcessingError, ValidationError,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// XML namespace of the `nodespace:` attributes
+pub const NODESPACE_NAMESPACE: &str = "https://github.com/malibio/nodespace-core-types/opml";
+
+const TYPE_ATTRIBUTE: &str = "nodespace:type";
+const CONTENT_ATTRIBUTE: &str = "nodespace:content";
+const METADATA_ATTRIBUTE: &str = "nodespace:metadata";
+const ID_ATTRIBUTE: &str = "nodespace:id";
+const UPDATED_ATTRIBUTE: &str = "nodespace:updated";
+const ORDER_KEY_ATTRIBUTE: &str = "nodespace:order_key";
+const REVISION_ATTRIBUTE: &str = "nodespace:revision";
+
+/// Key of the preserved outline attributes in `Node.metadata`
+const METADATA_KEY: &str = "opml";
+
+/// Deepest element nesting the reader accepts, counting `<opml>` and `<body>`
+pub const MAX_DEPTH: usize = 512;
+
+/// A parsed OPML document
+#[derive(Debug, Clone)]
+pub struct OpmlDocument {
+    /// Text of `<head><title>`
+    pub title: Option<String>,
+    /// Outline nodes in document order
+    pub nodes: Vec<Node>,
+}
+
+/// Parse an OPML document into nodes hanging under `parent_id`
+///
+/// Nodes are returned in document order with `parent_id`, sibling links and
+/// `root_id` set. Use [`import_opml`] to append them after existing children.
+pub fn parse_opml(
+    xml: &str,
+    parent_id: &NodeId,
+    root_id: &NodeId,
+) -> NodeSpaceResult<OpmlDocument> {
+    let document = XmlReader::new(xml).document().map_err(malformed)?;
+    if document.name != "opml" {
+        return Err(malformed(format!(
+            "root element is <{}>, expected <opml>",
+            document.name
+        )));
+    }
+    let title = document
+        .child("head")
+        .and_then(|head| head.child("title"))
+        .map(|title| title.text.trim().to_string());
+    let body = document
+        .child("body")
+        .ok_or_else(|| malformed("missing <body> element".to_string()))?;
+
+    let mut nodes = Vec::new();
+    collect_outlines(body, parent_id, root_id, &mut nodes)?;
+    Ok(OpmlDocument { title, nodes })
+}
+
+/// Import the outlines of an OPML document as the last children of `parent_id`
+///
+/// Outlines keep their exported IDs and timestamps. Those whose ID is already
+/// taken in `nodes`, e.g. when a document is imported twice, get a fresh ID.
+pub fn import_opml(
+    nodes: &[Node],
+    xml: &str,
+    parent_id: &NodeId,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let mut document = parse_opml(xml, parent_id, parent_id)?;
+
+    let existing: HashSet<&NodeId> = nodes.iter().map(|n| &n.id).collect();
+    let mut renamed = HashMap::new();
+    for node in &mut document.nodes {
+        if existing.contains(&node.id) {
+            let fresh = NodeId::new();
+            renamed.insert(node.id.clone(), fresh.clone());
+            node.id = fresh;
+        }
+    }
+    for node in &mut document.nodes {
+        for link in [
+            &mut node.parent_id,
+            &mut node.before_sibling,
+            &mut node.next_sibling,
+        ] {
+            if let Some(fresh) = link.as_ref().and_then(|id| renamed.get(id)) {
+                *link = Some(fresh.clone());
+            }
+        }
+    }
+
+    // Appending touches new nodes; keep the timestamps from the document
+    let updated_at: HashMap<NodeId, DateTime<Utc>> = document
+        .nodes
+        .iter()
+        .map(|n| (n.id.clone(), n.updated_at))
+        .collect();
+    let mut changes = append_subtrees(nodes, parent_id, document.nodes)?;
+    for node in &mut changes.updated {
+        if let Some(updated) = updated_at.get(&node.id) {
+            node.updated_at = *updated;
+        }
+    }
+    Ok(changes)
+}
+
+/// Export the subtree under `root_id` as an OPML 2.0 document
+///
+/// The root's text becomes the document title and its children the body
+/// outlines. Deleted nodes are left out.
+pub fn export_opml(nodes: &[Node], root_id: &NodeId) -> NodeSpaceResult<String> {
+    let tree = NodeTree::from_nodes(nodes.to_vec());
+    let root = tree
+        .get(root_id)
+        .ok_or_else(|| DatabaseError::not_found("Node", root_id.as_str()))?;
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<opml version=\"2.0\" xmlns:nodespace=\"{}\">\n",
+        NODESPACE_NAMESPACE
+    ));
+    xml.push_str("  <head>\n");
+    xml.push_str(&format!(
+        "    <title>{}</title>\n",
+        escape_text(&node_text(root))
+    ));
+    xml.push_str(&format!(
+        "    <dateCreated>{}</dateCreated>\n",
+        root.created_at.to_rfc2822()
+    ));
+    xml.push_str(&format!(
+        "    <dateModified>{}</dateModified>\n",
+        root.updated_at.to_rfc2822()
+    ));
+    xml.push_str("  </head>\n  <body>\n");
+    for child in tree.child_ids(root_id) {
+        write_outline(&tree, child, 2, &mut xml);
+    }
+    xml.push_str("  </body>\n</opml>\n");
+    Ok(xml)
+}
+
+fn collect_outlines(
+    element: &Element,
+    parent_id: &NodeId,
+    root_id: &NodeId,
+    nodes: &mut Vec<Node>,
+) -> NodeSpaceResult<()> {
+    let mut previous: Option<usize> = None;
+    for outline in element.children.iter().filter(|c| c.name == "outline") {
+        let mut node = outline_to_node(outline)?;
+        node.parent_id = Some(parent_id.clone());
+        node.root_id = Some(root_id.clone());
+        if let Some(previous) = previous {
+            node.before_sibling = Some(nodes[previous].id.clone());
+            nodes[previous].next_sibling = Some(node.id.clone());
+        }
+
+        let id = node.id.clone();
+        previous = Some(nodes.len());
+        nodes.push(node);
+        collect_outlines(outline, &id, root_id, nodes)?;
+    }
+    Ok(())
+}
+
+fn outline_to_node(outline: &Element) -> NodeSpaceResult<Node> {
+    let mut text = String::new();
+    let mut kind = NodeType::Text;
+    let mut content = None;
+    let mut metadata = None;
+    let mut id = None;
+    let mut created = None;
+    let mut updated = None;
+    let mut order_key = None;
+    let mut revision = None;
+    let mut attributes = Map::new();
+
+    for (name, value) in &outline.attributes {
+        match name.as_str() {
+            "text" => text = value.clone(),
+            "created" => match parse_timestamp(value) {
+                Some(timestamp) => created = Some(timestamp),
+                None => {
+                    attributes.insert(name.clone(), Value::String(value.clone()));
+                }
+            },
+            TYPE_ATTRIBUTE => kind = NodeType::custom(value)?,
+            CONTENT_ATTRIBUTE => content = Some(parse_json_attribute(name, value)?),
+            METADATA_ATTRIBUTE => metadata = Some(parse_json_attribute(name, value)?),
+            ID_ATTRIBUTE if !value.is_empty() => id = Some(NodeId::from_string(value.clone())),
+            UPDATED_ATTRIBUTE => {
+                updated = Some(parse_timestamp(value).ok_or_else(|| {
+                    ValidationError::invalid_format(name, "RFC 3339 timestamp", value)
+                })?)
+            }
+            ORDER_KEY_ATTRIBUTE => order_key = Some(value.clone()),
+            REVISION_ATTRIBUTE => {
+                revision = Some(value.parse().map_err(|_| {
+                    ValidationError::invalid_format(name, "non-negative integer", value)
+                })?)
+            }
+            _ => {
+                attributes.insert(name.clone(), Value::String(value.clone()));
+            }
+        }
+    }
+
+    let mut node = Node::of_kind(kind, content.unwrap_or(Value::String(text)));
+    if let Some(id) = id {
+        node.id = id;
+    }
+    if let Some(created) = created {
+        node.created_at = created;
+        node.updated_at = created;
+    }
+    if let Some(updated) = updated {
+        node.updated_at = updated;
+    }
+    node.order_key = order_key;
+    if let Some(revision) = revision {
+        node.revision = revision;
+    }
+    node.metadata = match (metadata, attributes.is_empty()) {
+        (metadata, true) => metadata,
+        (Some(Value::Object(mut map)), false) => {
+            map.insert(METADATA_KEY.to_string(), Value::Object(attributes));
+            Some(Value::Object(map))
+        }
+        (_, false) => Some(serde_json::json!({ METADATA_KEY: attributes })),
+    };
+    Ok(node)
+}
+
+fn parse_json_attribute(name: &str, value: &str) -> NodeSpaceResult<Value> {
+    serde_json::from_str(value)
+        .map_err(|_| ValidationError::invalid_format(name, "JSON", value).into())
+}
+
+fn write_outline(tree: &NodeTree, id: &NodeId, depth: usize, xml: &mut String) {
+    let Some(node) = tree.get(id) else {
+        return;
+    };
+    let indent = "  ".repeat(depth);
+
+    let mut attributes = vec![("text".to_string(), node_text(node))];
+    // A `created` value that did not parse on import is written back unchanged
+    let mut created = None;
+    // Metadata other than the preserved outline attributes
+    let extra_metadata = match node.metadata.as_ref() {
+        Some(Value::Object(metadata)) => {
+            let mut extra = metadata.clone();
+            if let Some(Value::Object(preserved)) = extra.remove(METADATA_KEY) {
+                for (name, value) in preserved {
+                    let value = value
+                        .as_str()
+                        .map_or_else(|| value.to_string(), str::to_string);
+                    if name == "created" {
+                        created = Some(value);
+                    } else {
+                        attributes.push((name, value));
+                    }
+                }
+            }
+            (!extra.is_empty()).then_some(Value::Object(extra))
+        }
+        other => other.cloned(),
+    };
+    attributes.push((
+        "created".to_string(),
+        created.unwrap_or_else(|| rfc3339(node.created_at)),
+    ));
+    attributes.push((ID_ATTRIBUTE.to_string(), node.id.to_string()));
+    attributes.push((UPDATED_ATTRIBUTE.to_string(), rfc3339(node.updated_at)));
+    if let Some(order_key) = node.order_key.as_ref() {
+        attributes.push((ORDER_KEY_ATTRIBUTE.to_string(), order_key.clone()));
+    }
+    attributes.push((REVISION_ATTRIBUTE.to_string(), node.revision.to_string()));
+    if node.r#type != NodeType::Text.as_str() {
+        attributes.push((TYPE_ATTRIBUTE.to_string(), node.r#type.clone()));
+    }
+    if !node.content.is_string() {
+        attributes.push((CONTENT_ATTRIBUTE.to_string(), node.content.to_string()));
+    }
+    if let Some(extra_metadata) = extra_metadata {
+        attributes.push((METADATA_ATTRIBUTE.to_string(), extra_metadata.to_string()));
+    }
+
+    xml.push_str(&indent);
+    xml.push_str("<outline");
+    for (name, value) in &attributes {
+        xml.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+    }
+
+    let children = tree.child_ids(id);
+    if children.is_empty() {
+        xml.push_str("/>\n");
+        return;
+    }
+    xml.push_str(">\n");
+    for child in children {
+        write_outline(tree, child, depth + 1, xml);
+    }
+    xml.push_str(&indent);
+    xml.push_str("</outline>\n");
+}
+
+fn rfc3339(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escape an attribute value, encoding whitespace that XML attribute value
+/// normalization would otherwise turn into spaces
+fn escape_attribute(value: &str) -> String {
+    escape_text(value)
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+        .replace('\r', "&#13;")
+        .replace('\t', "&#9;")
+}
+
+fn malformed(reason: String) -> crate::NodeSpaceError {
+    ProcessingError::SerializationFailed {
+        format: "OPML".to_string(),
+        reason,
+        data_type: "Node".to_string(),
+        fallback_formats: vec!["Markdown".to_string()],
+    }
+    .into()
+}
+
+/// An XML element with its attributes, child elements and concatenated text
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+struct XmlReader<'a> {
+    input: &'a str,
+    position: usize,
+    /// Number of elements currently open
+    depth: usize,
+}
+
+impl<'a> XmlReader<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input: input.strip_prefix('\u{feff}').unwrap_or(input),
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn document(&mut self) -> Result<Element, String> {
+        self.skip_prolog()?;
+        let root = self.element()?;
+        self.skip_prolog()?;
+        if self.position < self.input.len() {
+            return Err(self.error("unexpected content after the root element"));
+        }
+        Ok(root)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at byte {}", message, self.position)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skip past `terminator`, failing if it never appears
+    fn skip_past(&mut self, terminator: &str) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(terminator)
+            .ok_or_else(|| self.error(&format!("missing {:?}", terminator)))?;
+        self.position += end + terminator.len();
+        Ok(&rest[..end])
+    }
+
+    /// Skip whitespace, comments, processing instructions and a DOCTYPE
+    fn skip_prolog(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| c.is_whitespace() || "/>=".contains(c))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.position += length;
+        Ok(rest[..length].to_string())
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if !self.rest().starts_with(token) {
+            return Err(self.error(&format!("expected {:?}", token)));
+        }
+        self.position += token.len();
+        Ok(())
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(&format!("elements nested deeper than {}", MAX_DEPTH)));
+        }
+        self.expect("<")?;
+        let mut element = Element {
+            name: self.name()?,
+            ..Element::default()
+        };
+
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.position += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.position += 1;
+                break;
+            }
+            let name = self.name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => return Err(self.error("expected a quoted attribute value")),
+            };
+            self.position += 1;
+            let raw = self.skip_past(&quote.to_string())?;
+            if raw.contains('<') {
+                return Err(self.error("'<' in attribute value"));
+            }
+            let value = decode_references(&normalize_attribute(raw)).map_err(|e| self.error(&e))?;
+            element.attributes.push((name, value));
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(self.error(&format!("unclosed <{}>", element.name)));
+            }
+            if rest.starts_with("</") {
+                self.position += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(
+                        self.error(&format!("</{}> does not close <{}>", name, element.name))
+                    );
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.position += "<![CDATA[".len();
+                let data = self.skip_past("]]>")?;
+                element.text.push_str(data);
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                self.depth += 1;
+                let child = self.element();
+                self.depth -= 1;
+                element.children.push(child?);
+            } else {
+                let length = rest.find('<').unwrap_or(rest.len());
+                let text = decode_references(&rest[..length]).map_err(|e| self.error(&e))?;
+                element.text.push_str(&text);
+                self.position += length;
+            }
+        }
+    }
+}
+
+/// Attribute value normalization: literal whitespace characters become spaces
+fn normalize_attribute(raw: &str) -> String {
+    raw.replace("\r\n", " ").replace(['\n', '\r', '\t'], " ")
+}
+
+/// Replace the predefined entities and numeric character references
+fn decode_references(raw: &str) -> Result<String, String> {
+    let mut decoded = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| "unterminated character reference".to_string())?;
+        let reference = &rest[start + 1..start + end];
+        let character = match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => reference
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| reference.strip_prefix('#').map(|dec| dec.parse()))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        decoded.push(character.ok_or_else(|| format!("unknown reference &{};", reference))?);
+        rest = &rest[start + end + 1..];
+    }
+    decoded.push_str(rest);
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::validate_hierarchy;
+    use crate::ordering::keys_between;
+
+    fn page(id: &str) -> Node {
+        let mut page = Node::with_id(
+            NodeId::from_string(id.to_string()),
+            "text".to_string(),
+            Value::String(id.to_string()),
+        );
+        page.mark_as_hierarchy_root();
+        page
+    }
+
+    /// A page with two keyed children, the first with a nested task
+    fn workspace() -> Vec<Node> {
+        let page = page("page");
+        let keys = keys_between(None, None, 2).unwrap();
+        let mut first = Node::new("text".to_string(), Value::String("First".to_string()));
+        first.metadata = Some(serde_json::json!({ "source": "test", "opml": { "_note": "kept" } }));
+        let mut second = Node::new("text".to_string(), Value::String("Second".to_string()));
+        let mut task = Node::of_kind(NodeType::Task, serde_json::json!({ "title": "Nested" }));
+        task.revision = 4;
+
+        for (node, key) in [(&mut first, &keys[0]), (&mut second, &keys[1])] {
+            node.parent_id = Some(page.id.clone());
+            node.order_key = Some(key.clone());
+        }
+        first.next_sibling = Some(second.id.clone());
+        second.before_sibling = Some(first.id.clone());
+        task.parent_id = Some(first.id.clone());
+        for node in [&mut first, &mut second, &mut task] {
+            node.root_id = Some(page.id.clone());
+        }
+        vec![page, first, second, task]
+    }
+
+    fn imported(nodes: &[Node], xml: &str, parent: &NodeId) -> Vec<Node> {
+        let mut nodes = nodes.to_vec();
+        import_opml(&nodes, xml, parent)
+            .unwrap()
+            .apply_to(&mut nodes);
+        nodes
+    }
+
+    #[test]
+    fn round_trip_keeps_ids_timestamps_and_fields() {
+        let original = workspace();
+        let xml = export_opml(&original, &original[0].id).unwrap();
+
+        let copy = page("copy");
+        let nodes = imported(std::slice::from_ref(&copy), &xml, &copy.id);
+        assert_eq!(nodes.len(), original.len());
+        assert!(validate_hierarchy(&nodes).is_empty());
+
+        for before in &original[1..] {
+            let after = nodes.iter().find(|n| n.id == before.id).unwrap();
+            assert_eq!(after.r#type, before.r#type);
+            assert_eq!(after.content, before.content);
+            assert_eq!(after.metadata, before.metadata);
+            assert_eq!(after.created_at, before.created_at);
+            assert_eq!(after.updated_at, before.updated_at);
+            assert_eq!(after.order_key, before.order_key);
+            assert_eq!(after.revision, before.revision);
+            assert_eq!(after.before_sibling, before.before_sibling);
+            assert_eq!(after.next_sibling, before.next_sibling);
+            let parent = match before.parent_id.as_ref() {
+                Some(parent) if *parent == original[0].id => copy.id.clone(),
+                Some(parent) => parent.clone(),
+                None => unreachable!(),
+            };
+            assert_eq!(after.parent_id, Some(parent));
+            assert_eq!(after.root_id, Some(copy.id.clone()));
+        }
+        let body = |xml: &str| xml[xml.find("<body>").unwrap()..].to_string();
+        assert_eq!(body(&export_opml(&nodes, &copy.id).unwrap()), body(&xml));
+    }
+
+    #[test]
+    fn reimport_into_the_same_workspace_gets_fresh_ids() {
+        let original = workspace();
+        let page = original[0].id.clone();
+        let xml = export_opml(&original, &page).unwrap();
+        let nodes = imported(&original, &xml, &page);
+
+        assert_eq!(nodes.len(), 7);
+        assert!(validate_hierarchy(&nodes).is_empty());
+        let copies: Vec<&Node> = nodes[4..].iter().collect();
+        assert!(copies.iter().all(|n| original.iter().all(|o| o.id != n.id)));
+        let nested = copies.iter().find(|n| n.r#type == "task").unwrap();
+        let first = copies.iter().find(|n| n.content == "First").unwrap();
+        assert_eq!(nested.parent_id.as_ref(), Some(&first.id));
+        assert_eq!(first.before_sibling.as_ref(), Some(&original[2].id));
+    }
+
+    #[test]
+    fn imports_foreign_outlines() {
+        let xml = r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <head><title>Notes</title></head>
+  <body>
+    <outline text="Dated" created="Mon, 30 Jun 2025 10:00:00 GMT" _note="a &amp; b">
+      <outline text="Undated" created="someday"/>
+    </outline>
+  </body>
+</opml>"#;
+        let parent = NodeId::from_string("page".to_string());
+        let document = parse_opml(xml, &parent, &parent).unwrap();
+        assert_eq!(document.title.as_deref(), Some("Notes"));
+
+        let [dated, undated] = &document.nodes[..] else {
+            panic!("expected two outlines");
+        };
+        assert_eq!(dated.created_at.to_rfc3339(), "2025-06-30T10:00:00+00:00");
+        assert_eq!(dated.updated_at, dated.created_at);
+        assert_eq!(
+            dated.metadata,
+            Some(serde_json::json!({ "opml": { "_note": "a & b" } }))
+        );
+        assert_eq!(undated.parent_id.as_ref(), Some(&dated.id));
+        assert_eq!(
+            undated.metadata,
+            Some(serde_json::json!({ "opml": { "created": "someday" } }))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_nodespace_attributes() {
+        let parent = NodeId::from_string("page".to_string());
+        for outline in [
+            r#"<outline text="a" nodespace:updated="yesterday"/>"#,
+            r#"<outline text="a" nodespace:revision="-1"/>"#,
+            r#"<outline text="a" nodespace:content="{"/>"#,
+        ] {
+            let xml = format!("<opml><body>{}</body></opml>", outline);
+            assert!(parse_opml(&xml, &parent, &parent).is_err(), "{}", outline);
+        }
+    }
+
+    #[test]
+    fn unparsed_created_attribute_is_written_back() {
+        let xml = r#"<opml><body><outline text="Undated" created="someday"/></body></opml>"#;
+        let page = Node::with_id(
+            NodeId::from_string("page".to_string()),
+            "text".to_string(),
+            serde_json::json!("Page"),
+        );
+        let mut nodes = vec![page.clone()];
+        import_opml(&nodes, xml, &page.id)
+            .unwrap()
+            .apply_to(&mut nodes);
+
+        let exported = export_opml(&nodes, &page.id).unwrap();
+        assert!(exported.contains(r#"created="someday""#), "{}", exported);
+        assert_eq!(exported.matches("created=").count(), 1);
+
+        let document = parse_opml(&exported, &page.id, &page.id).unwrap();
+        assert_eq!(
+            document.nodes[0].metadata,
+            Some(serde_json::json!({ "opml": { "created": "someday" } }))
+        );
+    }
+
+    #[test]
+    fn rejects_documents_nested_too_deeply() {
+        let parent = NodeId::from_string("page".to_string());
+        let nested = |levels: usize| {
+            format!(
+                "<opml><body>{}{}</body></opml>",
+                "<outline text=\"x\">".repeat(levels),
+                "</outline>".repeat(levels)
+            )
+        };
+
+        let document = parse_opml(&nested(MAX_DEPTH - 2), &parent, &parent).unwrap();
+        assert_eq!(document.nodes.len(), MAX_DEPTH - 2);
+
+        let error = parse_opml(&nested(MAX_DEPTH - 1), &parent, &parent).unwrap_err();
+        assert!(error.to_string().contains("nested deeper"), "{}", error);
+        assert!(parse_opml(&nested(100_000), &parent, &parent).is_err());
+    }
+}
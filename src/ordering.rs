@@ -0,0 +1,366 @@
+//! Fractional-index sibling ordering
+//!
+//! Reordering with `before_sibling` / `next_sibling` rewrites three nodes per move
+//! and conflicts easily under concurrent edits. An `order_key` is a
+//! lexicographically sortable string: moving a node only requires a new key
+//! between its new neighbours. Keys use base-62 digits (`0-9A-Za-z`), are compared
+//! byte-wise, and never end in `0`, so a key between any two distinct keys always
+//! exists.
+//!
+//! The conversion functions allow a gradual migration between the linked-list
+//! representation and keys. Tombstoned nodes do not take part in sibling order:
+//! they keep the links and key recorded at deletion so a restore can find its
+//! place again.
+
+use crate::hierarchy::{HierarchyChangeSet, Staging};
+use crate::tree::{order_by_links, order_siblings};
+use crate::{Node, NodeId, NodeSpaceResult, ValidationError};
+use std::collections::HashMap;
+
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Check that a string is a well-formed order key
+pub fn validate_order_key(key: &str) -> NodeSpaceResult<()> {
+    let well_formed =
+        !key.is_empty() && key.bytes().all(|b| b.is_ascii_alphanumeric()) && !key.ends_with('0');
+    if well_formed {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidFormat {
+            field: "order_key".to_string(),
+            expected: "non-empty base-62 string not ending in '0'".to_string(),
+            actual: key.to_string(),
+            examples: vec!["V".to_string(), "a0V".to_string(), "Zk".to_string()],
+        }
+        .into())
+    }
+}
+
+/// Generate a key that sorts strictly between two neighbours
+///
+/// `None` stands for the start (for `before`) or the end (for `after`) of the
+/// sibling list.
+pub fn key_between(before: Option<&str>, after: Option<&str>) -> NodeSpaceResult<String> {
+    if let Some(before) = before {
+        validate_order_key(before)?;
+    }
+    if let Some(after) = after {
+        validate_order_key(after)?;
+    }
+    if let (Some(before), Some(after)) = (before, after) {
+        if before >= after {
+            return Err(ValidationError::InvalidFormat {
+                field: "order_key".to_string(),
+                expected: format!("a key greater than {:?}", before),
+                actual: after.to_string(),
+                examples: vec![],
+            }
+            .into());
+        }
+    }
+
+    let key = midpoint(before.unwrap_or("").as_bytes(), after.map(str::as_bytes));
+    Ok(String::from_utf8(key).expect("order key digits are ASCII"))
+}
+
+/// Generate `count` evenly distributed keys between two neighbours
+pub fn keys_between(
+    before: Option<&str>,
+    after: Option<&str>,
+    count: usize,
+) -> NodeSpaceResult<Vec<String>> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let mid = key_between(before, after)?;
+    let left_count = count / 2;
+    let mut keys = keys_between(before, Some(&mid), left_count)?;
+    let right = keys_between(Some(&mid), after, count - left_count - 1)?;
+    keys.push(mid);
+    keys.extend(right);
+    Ok(keys)
+}
+
+/// Convert linked-list sibling order into order keys
+///
+/// Every sibling group under a parent gets fresh, evenly distributed keys that
+/// follow the current `before_sibling` / `next_sibling` chain. Only nodes whose key
+/// changes are returned. Top-level nodes are left untouched.
+pub fn assign_order_keys(nodes: &[Node]) -> NodeSpaceResult<HierarchyChangeSet> {
+    let by_id: HashMap<&NodeId, &Node> = nodes.iter().map(|n| (&n.id, n)).collect();
+    let mut staging = Staging::new(nodes);
+
+    for members in sibling_groups(nodes) {
+        let ordered = order_by_links(&members, |id| by_id[id]);
+        write_keys(&mut staging, &ordered)?;
+    }
+
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Replace the order keys of one sibling group with fresh, evenly spaced keys
+///
+/// Keys grow longer as nodes are repeatedly inserted between the same neighbours;
+/// rebalancing restores short keys while keeping the current order.
+pub fn rebalance_order_keys(
+    nodes: &[Node],
+    parent: &NodeId,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let by_id: HashMap<&NodeId, &Node> = nodes.iter().map(|n| (&n.id, n)).collect();
+    let members: Vec<NodeId> = nodes
+        .iter()
+        .filter(|n| n.parent_id.as_ref() == Some(parent) && !n.is_deleted())
+        .map(|n| n.id.clone())
+        .collect();
+
+    let mut staging = Staging::new(nodes);
+    let ordered = order_siblings(&members, |id| by_id[id]);
+    write_keys(&mut staging, &ordered)?;
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Rewrite sibling links to match the effective sibling order
+///
+/// Groups where every node has an `order_key` are linked in key order; other groups
+/// are relinked along their existing chain, which also repairs broken
+/// back-links. Only nodes whose links change are returned.
+pub fn relink_siblings(nodes: &[Node]) -> NodeSpaceResult<HierarchyChangeSet> {
+    let by_id: HashMap<&NodeId, &Node> = nodes.iter().map(|n| (&n.id, n)).collect();
+    let mut staging = Staging::new(nodes);
+
+    for members in sibling_groups(nodes) {
+        let ordered = order_siblings(&members, |id| by_id[id]);
+        for (i, id) in ordered.iter().enumerate() {
+            let node = staging.edit(id)?;
+            node.before_sibling = i.checked_sub(1).map(|prev| ordered[prev].clone());
+            node.next_sibling = ordered.get(i + 1).cloned();
+        }
+    }
+
+    Ok(staging.finish(Vec::new()))
+}
+
+/// Sort nodes by their order key, keeping nodes without a key last
+pub fn sort_by_order_key(nodes: &mut [Node]) {
+    nodes.sort_by(|a, b| match (&a.order_key, &b.order_key) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
+fn write_keys(staging: &mut Staging, ordered: &[NodeId]) -> NodeSpaceResult<()> {
+    let keys = keys_between(None, None, ordered.len())?;
+    for (id, key) in ordered.iter().zip(keys) {
+        staging.edit(id)?.order_key = Some(key);
+    }
+    Ok(())
+}
+
+/// Group live nodes by `parent_id`, in order of first appearance
+fn sibling_groups(nodes: &[Node]) -> Vec<Vec<NodeId>> {
+    let mut index: HashMap<&NodeId, usize> = HashMap::new();
+    let mut groups: Vec<Vec<NodeId>> = Vec::new();
+    for node in nodes.iter().filter(|n| !n.is_deleted()) {
+        let Some(parent) = node.parent_id.as_ref() else {
+            continue;
+        };
+        let slot = *index.entry(parent).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[slot].push(node.id.clone());
+    }
+    groups
+}
+
+fn digit_value(digit: u8) -> usize {
+    DIGITS
+        .iter()
+        .position(|&d| d == digit)
+        .expect("order key validated before use")
+}
+
+/// Midpoint between two validated keys, `b = None` meaning the end of the range
+fn midpoint(a: &[u8], b: Option<&[u8]>) -> Vec<u8> {
+    let zero = DIGITS[0];
+
+    if let Some(b) = b {
+        // Strip the common prefix, padding `a` with zeros
+        let shared = (0..b.len())
+            .take_while(|&i| a.get(i).copied().unwrap_or(zero) == b[i])
+            .count();
+        if shared > 0 {
+            let mut key = b[..shared].to_vec();
+            key.extend(midpoint(a.get(shared..).unwrap_or(&[]), Some(&b[shared..])));
+            return key;
+        }
+    }
+
+    let digit_a = a.first().map(|&d| digit_value(d)).unwrap_or(0);
+    let digit_b = b
+        .and_then(|b| b.first())
+        .map(|&d| digit_value(d))
+        .unwrap_or(DIGITS.len());
+
+    if digit_b - digit_a > 1 {
+        vec![DIGITS[(digit_a + digit_b).div_ceil(2)]]
+    } else if let Some(b) = b.filter(|b| b.len() > 1) {
+        vec![b[0]]
+    } else {
+        let mut key = vec![DIGITS[digit_a]];
+        key.extend(midpoint(a.get(1..).unwrap_or(&[]), None));
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn between(before: Option<&str>, after: Option<&str>) -> String {
+        let key = key_between(before, after).unwrap();
+        validate_order_key(&key).unwrap();
+        if let Some(before) = before {
+            assert!(before < key.as_str(), "{before:?} < {key:?}");
+        }
+        if let Some(after) = after {
+            assert!(key.as_str() < after, "{key:?} < {after:?}");
+        }
+        key
+    }
+
+    fn child(id: &str, parent: &NodeId) -> Node {
+        let mut node = Node::with_id(
+            NodeId::from_string(id.to_string()),
+            "text".to_string(),
+            serde_json::json!(id),
+        );
+        node.parent_id = Some(parent.clone());
+        node.root_id = Some(parent.clone());
+        node
+    }
+
+    #[test]
+    fn key_between_adjacent_keys() {
+        between(Some("a"), Some("b"));
+        between(Some("Z"), Some("a"));
+        between(Some("a"), Some("a1"));
+        between(Some("az"), Some("b"));
+        between(Some("a0V"), Some("a1"));
+    }
+
+    #[test]
+    fn key_between_rejects_unordered_or_malformed_keys() {
+        assert!(key_between(Some("b"), Some("a")).is_err());
+        assert!(key_between(Some("a"), Some("a")).is_err());
+        assert!(key_between(Some("a0"), None).is_err());
+        assert!(key_between(None, Some("")).is_err());
+    }
+
+    #[test]
+    fn prepending_before_the_minimum_key() {
+        let mut first = between(None, None);
+        for _ in 0..200 {
+            first = between(None, Some(&first));
+        }
+    }
+
+    #[test]
+    fn appending_repeatedly() {
+        let mut last = between(None, None);
+        for _ in 0..200 {
+            last = between(Some(&last), None);
+        }
+    }
+
+    #[test]
+    fn inserting_repeatedly_between_the_same_neighbours() {
+        let (low, mut high) = ("a".to_string(), "b".to_string());
+        for _ in 0..100 {
+            high = between(Some(&low), Some(&high));
+        }
+    }
+
+    #[test]
+    fn keys_between_are_strictly_increasing() {
+        for count in [0, 1, 2, 7, 62, 500] {
+            let keys = keys_between(Some("1"), Some("y"), count).unwrap();
+            assert_eq!(keys.len(), count);
+            assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(keys.iter().all(|k| "1" < k.as_str() && k.as_str() < "y"));
+        }
+        let keys = keys_between(None, None, 100).unwrap();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn rebalance_keeps_order_and_shortens_keys() {
+        let parent = NodeId::from_string("parent".to_string());
+        let mut low = "a".to_string();
+        let high = "b".to_string();
+        let mut nodes = Vec::new();
+        for i in 0..20 {
+            low = between(Some(&low), Some(&high));
+            let mut node = child(&format!("n{i:02}"), &parent);
+            node.order_key = Some(low.clone());
+            nodes.push(node);
+        }
+        nodes.reverse();
+
+        let changes = rebalance_order_keys(&nodes, &parent).unwrap();
+        changes.apply_to(&mut nodes);
+        sort_by_order_key(&mut nodes);
+
+        let ids: Vec<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+        let expected: Vec<String> = (0..20).map(|i| format!("n{i:02}")).collect();
+        assert_eq!(ids, expected);
+        assert!(nodes
+            .iter()
+            .all(|n| n.order_key.as_ref().unwrap().len() <= 2));
+    }
+
+    /// Children `a`, `b`, `c` of `parent` with `b` tombstoned and unlinked
+    fn siblings_with_tombstone() -> Vec<Node> {
+        let parent = NodeId::from_string("parent".to_string());
+        let mut nodes = vec![
+            child("a", &parent),
+            child("b", &parent),
+            child("c", &parent),
+        ];
+        let ids: Vec<NodeId> = nodes.iter().map(|n| n.id.clone()).collect();
+        nodes[0].next_sibling = Some(ids[1].clone());
+        nodes[1].before_sibling = Some(ids[0].clone());
+        nodes[1].next_sibling = Some(ids[2].clone());
+        nodes[2].before_sibling = Some(ids[1].clone());
+
+        let changes = crate::hierarchy::tombstone_subtree(&nodes, &ids[1], "alice").unwrap();
+        changes.apply_to(&mut nodes);
+        nodes
+    }
+
+    #[test]
+    fn tombstones_are_left_out_of_sibling_groups() {
+        let mut nodes = siblings_with_tombstone();
+        let tombstone = nodes[1].clone();
+        assert!(tombstone.is_deleted());
+
+        assert!(relink_siblings(&nodes).unwrap().is_empty());
+
+        let changes = assign_order_keys(&nodes).unwrap();
+        assert!(changes.updated.iter().all(|n| n.id != tombstone.id));
+        changes.apply_to(&mut nodes);
+        assert!(nodes[0].order_key < nodes[2].order_key);
+        assert_eq!(nodes[1].order_key, None);
+
+        nodes[1].order_key = Some("V".to_string());
+        let parent = NodeId::from_string("parent".to_string());
+        let changes = rebalance_order_keys(&nodes, &parent).unwrap();
+        assert!(changes.updated.iter().all(|n| n.id != tombstone.id));
+        changes.apply_to(&mut nodes);
+        assert_eq!(nodes[1].before_sibling, tombstone.before_sibling);
+        assert_eq!(nodes[1].next_sibling, tombstone.next_sibling);
+        assert_eq!(nodes[1].order_key.as_deref(), Some("V"));
+    }
+}
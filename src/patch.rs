@@ -0,0 +1,750 @@
+//! JSON Patch (RFC 6902) and JSON Merge Patch (RFC 7386) support for nodes
+//!
+//! Services exchange [`JsonPatch`] documents instead of whole nodes for small
+//! edits, and REST-style clients send merge patches for partial updates. Node
+//! patches cover `type`, `content`, `metadata` and the pointer fields; identity and
+//! timestamps are managed by the node itself.
+
+use crate::{Node, NodeId, NodeSpaceError, NodeSpaceResult, ProcessingError, ValidationError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Top-level `Node` fields that node patches may read and write
+pub const PATCHABLE_NODE_FIELDS: &[&str] = &[
+    "type",
+    "content",
+    "metadata",
+    "parent_id",
+    "before_sibling",
+    "next_sibling",
+    "root_id",
+    "order_key",
+];
+
+/// Single JSON Patch operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+impl PatchOperation {
+    /// Target path of the operation
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Add { path, .. }
+            | Self::Remove { path }
+            | Self::Replace { path, .. }
+            | Self::Move { path, .. }
+            | Self::Copy { path, .. }
+            | Self::Test { path, .. } => path,
+        }
+    }
+
+    /// Source path for `move` and `copy` operations
+    pub fn from_path(&self) -> Option<&str> {
+        match self {
+            Self::Move { from, .. } | Self::Copy { from, .. } => Some(from),
+            _ => None,
+        }
+    }
+}
+
+/// Ordered list of JSON Patch operations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JsonPatch(pub Vec<PatchOperation>);
+
+impl JsonPatch {
+    /// Create an empty patch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check if the patch contains no operations
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Number of operations in the patch
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Append an operation
+    pub fn push(&mut self, operation: PatchOperation) {
+        self.0.push(operation);
+    }
+
+    /// Iterate over the operations
+    pub fn operations(&self) -> impl Iterator<Item = &PatchOperation> {
+        self.0.iter()
+    }
+}
+
+/// Produce a patch that turns `from` into `to`
+pub fn diff(from: &Value, to: &Value) -> JsonPatch {
+    let mut patch = JsonPatch::new();
+    diff_at("", from, to, &mut patch);
+    patch
+}
+
+/// Produce a patch between two versions of a node
+///
+/// Only [`PATCHABLE_NODE_FIELDS`] are compared.
+pub fn diff_nodes(from: &Node, to: &Node) -> NodeSpaceResult<JsonPatch> {
+    let from = node_to_value(from)?;
+    let to = node_to_value(to)?;
+    let mut patch = JsonPatch::new();
+    for field in PATCHABLE_NODE_FIELDS {
+        let path = format!("/{}", escape_token(field));
+        diff_at(
+            &path,
+            from.get(field).unwrap_or(&Value::Null),
+            to.get(field).unwrap_or(&Value::Null),
+            &mut patch,
+        );
+    }
+    Ok(patch)
+}
+
+/// Apply a patch to a JSON document
+///
+/// The patch is applied atomically: if any operation fails (including a failed
+/// `test`), the document is left unchanged.
+pub fn apply_patch(target: &mut Value, patch: &JsonPatch) -> NodeSpaceResult<()> {
+    let mut working = target.clone();
+    for operation in patch.operations() {
+        apply_operation(&mut working, operation)?;
+    }
+    *target = working;
+    Ok(())
+}
+
+/// Apply a JSON Merge Patch (RFC 7386) to a JSON document
+///
+/// Object members in the patch are merged recursively, `null` removes a member and
+/// any other value replaces the target.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("target was replaced with an object above");
+    };
+    for (key, value) in members {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+impl Node {
+    /// Produce a JSON Patch that turns this node into `other`
+    pub fn diff(&self, other: &Node) -> NodeSpaceResult<JsonPatch> {
+        diff_nodes(self, other)
+    }
+
+    /// Apply a JSON Patch to this node and update its timestamp
+    ///
+    /// Operations may only touch [`PATCHABLE_NODE_FIELDS`]. The revision is bumped
+    /// when the patch changed the node. The node is left unchanged if any
+    /// operation fails.
+    pub fn apply_patch(&mut self, patch: &JsonPatch) -> NodeSpaceResult<()> {
+        for operation in patch.operations() {
+            ensure_patchable(operation.path())?;
+            if let Some(from) = operation.from_path() {
+                ensure_patchable(from)?;
+            }
+        }
+
+        let original = node_to_value(self)?;
+        let mut value = original.clone();
+        apply_patch(&mut value, patch)?;
+        if value == original {
+            self.touch();
+            return Ok(());
+        }
+        *self =
+            serde_json::from_value(value).map_err(|e| ProcessingError::SerializationFailed {
+                format: "JSON".to_string(),
+                reason: format!("Patched document is not a valid Node: {}", e),
+                data_type: "Node".to_string(),
+                fallback_formats: vec![],
+            })?;
+        self.bump_revision();
+        Ok(())
+    }
+
+    /// Apply a JSON Merge Patch (RFC 7386) and update the timestamp
+    ///
+    /// `content` and `metadata` are merged recursively following RFC 7386. The
+    /// other top-level members (`type`, `parent_id`, `before_sibling`,
+    /// `next_sibling`, `root_id`, `order_key`) are replaced as a whole, with `null`
+    /// clearing optional fields. The revision is bumped when the patch changed the
+    /// node, and the node is left unchanged if the patch is invalid.
+    ///
+    /// ```rust
+    /// use nodespace_core_types::Node;
+    /// use serde_json::json;
+    ///
+    /// let mut node = Node::new("text".to_string(), json!({"title": "Draft", "done": false}));
+    /// node.merge_patch(&json!({"content": {"done": true}, "parent_id": "parent-1"}))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(node.content, json!({"title": "Draft", "done": true}));
+    /// assert_eq!(node.parent_id.unwrap().as_str(), "parent-1");
+    /// ```
+    pub fn merge_patch(&mut self, patch: &Value) -> NodeSpaceResult<()> {
+        let Value::Object(members) = patch else {
+            return Err(ValidationError::invalid_format(
+                "patch",
+                "JSON object",
+                &json_type_name(patch),
+            )
+            .into());
+        };
+
+        let mut updated = self.clone();
+        for (key, value) in members {
+            match key.as_str() {
+                "content" => merge_patch(&mut updated.content, value),
+                "metadata" => {
+                    let mut metadata = updated.metadata.take().unwrap_or(Value::Null);
+                    merge_patch(&mut metadata, value);
+                    updated.metadata = Some(metadata).filter(|m| !m.is_null());
+                }
+                "type" => match value.as_str() {
+                    Some(node_type) => updated.r#type = node_type.to_string(),
+                    None => {
+                        return Err(ValidationError::invalid_format(
+                            "type",
+                            "string",
+                            &json_type_name(value),
+                        )
+                        .into())
+                    }
+                },
+                "parent_id" => updated.parent_id = merge_string(key, value)?.map(NodeId::from),
+                "before_sibling" => {
+                    updated.before_sibling = merge_string(key, value)?.map(NodeId::from)
+                }
+                "next_sibling" => {
+                    updated.next_sibling = merge_string(key, value)?.map(NodeId::from)
+                }
+                "root_id" => updated.root_id = merge_string(key, value)?.map(NodeId::from),
+                "order_key" => updated.order_key = merge_string(key, value)?,
+                _ => {
+                    return Err(ValidationError::InvalidFormat {
+                        field: key.clone(),
+                        expected: "a patchable Node field".to_string(),
+                        actual: key.clone(),
+                        examples: PATCHABLE_NODE_FIELDS
+                            .iter()
+                            .map(|f| f.to_string())
+                            .collect(),
+                    }
+                    .into())
+                }
+            }
+        }
+
+        let changed = updated.r#type != self.r#type
+            || updated.content != self.content
+            || updated.metadata != self.metadata
+            || updated.parent_id != self.parent_id
+            || updated.before_sibling != self.before_sibling
+            || updated.next_sibling != self.next_sibling
+            || updated.root_id != self.root_id
+            || updated.order_key != self.order_key;
+        *self = updated;
+        if changed {
+            self.bump_revision();
+        } else {
+            self.touch();
+        }
+        Ok(())
+    }
+}
+
+/// Interpret a merge patch member that replaces an optional string field
+fn merge_string(field: &str, value: &Value) -> NodeSpaceResult<Option<String>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(text) => Ok(Some(text.clone())),
+        other => {
+            Err(
+                ValidationError::invalid_format(field, "string or null", &json_type_name(other))
+                    .into(),
+            )
+        }
+    }
+}
+
+fn json_type_name(value: &Value) -> String {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+    .to_string()
+}
+
+fn node_to_value(node: &Node) -> NodeSpaceResult<Value> {
+    serde_json::to_value(node).map_err(|e| {
+        ProcessingError::SerializationFailed {
+            format: "JSON".to_string(),
+            reason: e.to_string(),
+            data_type: "Node".to_string(),
+            fallback_formats: vec![],
+        }
+        .into()
+    })
+}
+
+fn ensure_patchable(path: &str) -> NodeSpaceResult<()> {
+    let field = parse_pointer(path)?.into_iter().next();
+    match field {
+        Some(field) if PATCHABLE_NODE_FIELDS.contains(&field.as_str()) => Ok(()),
+        _ => Err(ValidationError::InvalidFormat {
+            field: "path".to_string(),
+            expected: "a pointer into a patchable Node field".to_string(),
+            actual: path.to_string(),
+            examples: PATCHABLE_NODE_FIELDS
+                .iter()
+                .map(|f| format!("/{}", f))
+                .collect(),
+        }
+        .into()),
+    }
+}
+
+fn diff_at(path: &str, from: &Value, to: &Value, patch: &mut JsonPatch) {
+    match (from, to) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, old) in a {
+                let child = format!("{}/{}", path, escape_token(key));
+                match b.get(key) {
+                    Some(new) => diff_at(&child, old, new, patch),
+                    None => patch.push(PatchOperation::Remove { path: child }),
+                }
+            }
+            for (key, new) in b {
+                if !a.contains_key(key) {
+                    patch.push(PatchOperation::Add {
+                        path: format!("{}/{}", path, escape_token(key)),
+                        value: new.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            let shared = a.len().min(b.len());
+            for i in 0..shared {
+                diff_at(&format!("{}/{}", path, i), &a[i], &b[i], patch);
+            }
+            // Remove from the end so earlier indices stay valid
+            for i in (shared..a.len()).rev() {
+                patch.push(PatchOperation::Remove {
+                    path: format!("{}/{}", path, i),
+                });
+            }
+            for (i, value) in b.iter().enumerate().skip(shared) {
+                patch.push(PatchOperation::Add {
+                    path: format!("{}/{}", path, i),
+                    value: value.clone(),
+                });
+            }
+        }
+        _ if from != to => patch.push(PatchOperation::Replace {
+            path: path.to_string(),
+            value: to.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn apply_operation(doc: &mut Value, operation: &PatchOperation) -> NodeSpaceResult<()> {
+    match operation {
+        PatchOperation::Add { path, value } => add(doc, path, value.clone()),
+        PatchOperation::Remove { path } => remove(doc, path).map(|_| ()),
+        PatchOperation::Replace { path, value } => {
+            *lookup_mut(doc, path)? = value.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if path != from && path.starts_with(&format!("{}/", from)) {
+                return Err(invalid_path(path, "a location outside of the moved value"));
+            }
+            let value = remove(doc, from)?;
+            add(doc, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = lookup(doc, from)?.clone();
+            add(doc, path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            let actual = lookup(doc, path).ok();
+            if actual == Some(value) {
+                Ok(())
+            } else {
+                Err(ValidationError::PatchTestFailed {
+                    path: path.clone(),
+                    expected: value.clone(),
+                    actual: actual.cloned().unwrap_or(Value::Null),
+                }
+                .into())
+            }
+        }
+    }
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> NodeSpaceResult<()> {
+    let mut tokens = parse_pointer(path)?;
+    let Some(last) = tokens.pop() else {
+        *doc = value;
+        return Ok(());
+    };
+    match resolve_mut(doc, &tokens, path)? {
+        Value::Object(map) => {
+            map.insert(last, value);
+            Ok(())
+        }
+        Value::Array(items) => {
+            let index = if last == "-" {
+                items.len()
+            } else {
+                parse_index(&last, path)?
+            };
+            if index > items.len() {
+                return Err(invalid_path(path, "an array index within bounds"));
+            }
+            items.insert(index, value);
+            Ok(())
+        }
+        _ => Err(invalid_path(
+            path,
+            "a path whose parent is an object or array",
+        )),
+    }
+}
+
+fn remove(doc: &mut Value, path: &str) -> NodeSpaceResult<Value> {
+    let mut tokens = parse_pointer(path)?;
+    let Some(last) = tokens.pop() else {
+        return Err(invalid_path(path, "a non-root path"));
+    };
+    match resolve_mut(doc, &tokens, path)? {
+        Value::Object(map) => map
+            .remove(&last)
+            .ok_or_else(|| invalid_path(path, "an existing object member")),
+        Value::Array(items) => {
+            let index = parse_index(&last, path)?;
+            if index >= items.len() {
+                return Err(invalid_path(path, "an existing array element"));
+            }
+            Ok(items.remove(index))
+        }
+        _ => Err(invalid_path(
+            path,
+            "a path whose parent is an object or array",
+        )),
+    }
+}
+
+fn lookup<'a>(doc: &'a Value, path: &str) -> NodeSpaceResult<&'a Value> {
+    let mut current = doc;
+    for token in parse_pointer(path)? {
+        current = match current {
+            Value::Object(map) => map.get(&token),
+            Value::Array(items) => items.get(parse_index(&token, path)?),
+            _ => None,
+        }
+        .ok_or_else(|| invalid_path(path, "an existing location"))?;
+    }
+    Ok(current)
+}
+
+fn lookup_mut<'a>(doc: &'a mut Value, path: &str) -> NodeSpaceResult<&'a mut Value> {
+    let tokens = parse_pointer(path)?;
+    resolve_mut(doc, &tokens, path)
+}
+
+fn resolve_mut<'a>(
+    doc: &'a mut Value,
+    tokens: &[String],
+    path: &str,
+) -> NodeSpaceResult<&'a mut Value> {
+    let mut current = doc;
+    for token in tokens {
+        current = match current {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(items) => items.get_mut(parse_index(token, path)?),
+            _ => None,
+        }
+        .ok_or_else(|| invalid_path(path, "an existing location"))?;
+    }
+    Ok(current)
+}
+
+/// Split a JSON Pointer (RFC 6901) into unescaped reference tokens
+fn parse_pointer(path: &str) -> NodeSpaceResult<Vec<String>> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = path.strip_prefix('/') else {
+        return Err(invalid_path(path, "a JSON Pointer starting with '/'"));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn parse_index(token: &str, path: &str) -> NodeSpaceResult<usize> {
+    let canonical = token == "0" || (!token.starts_with('0') && !token.is_empty());
+    if !canonical || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid_path(path, "a non-negative array index"));
+    }
+    token
+        .parse()
+        .map_err(|_| invalid_path(path, "a non-negative array index"))
+}
+
+fn invalid_path(path: &str, expected: &str) -> NodeSpaceError {
+    ValidationError::InvalidFormat {
+        field: "path".to_string(),
+        expected: expected.to_string(),
+        actual: path.to_string(),
+        examples: vec!["/content/title".to_string(), "/metadata/tags/0".to_string()],
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patch(operations: Value) -> JsonPatch {
+        serde_json::from_value(operations).unwrap()
+    }
+
+    fn patched(mut doc: Value, operations: Value) -> NodeSpaceResult<Value> {
+        apply_patch(&mut doc, &patch(operations))?;
+        Ok(doc)
+    }
+
+    #[test]
+    fn failed_operation_rolls_back_the_whole_patch() {
+        let original = json!({"title": "Draft", "tags": ["a"]});
+        let mut doc = original.clone();
+        let result = apply_patch(
+            &mut doc,
+            &patch(json!([
+                {"op": "replace", "path": "/title", "value": "Final"},
+                {"op": "add", "path": "/tags/-", "value": "b"},
+                {"op": "remove", "path": "/missing"}
+            ])),
+        );
+        assert!(result.is_err());
+        assert_eq!(doc, original);
+    }
+
+    #[test]
+    fn failed_test_operation_rolls_back() {
+        let original = json!({"title": "Draft"});
+        let mut doc = original.clone();
+        let result = apply_patch(
+            &mut doc,
+            &patch(json!([
+                {"op": "replace", "path": "/title", "value": "Final"},
+                {"op": "test", "path": "/title", "value": "Draft"}
+            ])),
+        );
+        assert!(matches!(
+            result,
+            Err(NodeSpaceError::Validation(
+                ValidationError::PatchTestFailed { .. }
+            ))
+        ));
+        assert_eq!(doc, original);
+    }
+
+    #[test]
+    fn array_append_and_index_handling() {
+        let doc = json!({"items": [1, 2]});
+        assert_eq!(
+            patched(
+                doc.clone(),
+                json!([{"op": "add", "path": "/items/-", "value": 3}])
+            )
+            .unwrap(),
+            json!({"items": [1, 2, 3]})
+        );
+        assert_eq!(
+            patched(
+                doc.clone(),
+                json!([{"op": "add", "path": "/items/0", "value": 0}])
+            )
+            .unwrap(),
+            json!({"items": [0, 1, 2]})
+        );
+        assert_eq!(
+            patched(
+                doc.clone(),
+                json!([{"op": "add", "path": "/items/2", "value": 3}])
+            )
+            .unwrap(),
+            json!({"items": [1, 2, 3]})
+        );
+        assert_eq!(
+            patched(doc.clone(), json!([{"op": "remove", "path": "/items/0"}])).unwrap(),
+            json!({"items": [2]})
+        );
+        assert_eq!(
+            patched(
+                doc.clone(),
+                json!([{"op": "move", "from": "/items/0", "path": "/items/-"}])
+            )
+            .unwrap(),
+            json!({"items": [2, 1]})
+        );
+
+        for invalid in [
+            json!([{"op": "add", "path": "/items/3", "value": 0}]),
+            json!([{"op": "add", "path": "/items/01", "value": 0}]),
+            json!([{"op": "remove", "path": "/items/2"}]),
+            json!([{"op": "remove", "path": "/items/-"}]),
+            json!([{"op": "replace", "path": "/items/-1", "value": 0}]),
+        ] {
+            assert!(patched(doc.clone(), invalid.clone()).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn pointer_escapes() {
+        let doc = json!({"a/b": 1, "m~n": 2, "~1": 3});
+        assert_eq!(
+            patched(
+                doc.clone(),
+                json!([
+                    {"op": "replace", "path": "/a~1b", "value": 10},
+                    {"op": "replace", "path": "/m~0n", "value": 20},
+                    {"op": "remove", "path": "/~01"}
+                ])
+            )
+            .unwrap(),
+            json!({"a/b": 10, "m~n": 20})
+        );
+
+        let generated = diff(&doc, &json!({"a/b": 1, "m~n": 5, "~1": 3}));
+        assert_eq!(
+            generated,
+            JsonPatch(vec![PatchOperation::Replace {
+                path: "/m~0n".to_string(),
+                value: json!(5),
+            }])
+        );
+    }
+
+    #[test]
+    fn diff_then_apply_round_trips() {
+        let cases = [
+            (json!({"a": 1}), json!({"a": 2, "b": [1, 2]})),
+            (json!({"list": [1, 2, 3, 4]}), json!({"list": [1, 5]})),
+            (json!({"list": [1]}), json!({"list": [1, {"x": null}, 3]})),
+            (json!({"nested": {"x/y": {"~": 1}}}), json!({"nested": {}})),
+            (json!([1, 2]), json!({"now": "object"})),
+            (json!("text"), json!("text")),
+        ];
+        for (from, to) in cases {
+            let generated = diff(&from, &to);
+            let mut doc = from.clone();
+            apply_patch(&mut doc, &generated).unwrap();
+            assert_eq!(doc, to, "{from} -> {to}");
+        }
+        assert!(diff(&json!({"a": [1]}), &json!({"a": [1]})).is_empty());
+    }
+
+    #[test]
+    fn node_diff_then_apply_round_trips() {
+        let before = Node::new("text".to_string(), json!({"content": "Hello"}));
+        let mut after = before.clone();
+        after.content = json!({"content": "Hello world", "tags": ["x"]});
+        after.parent_id = Some(NodeId::from_string("parent".to_string()));
+        after.metadata = Some(json!({"source": "import"}));
+
+        let mut node = before.clone();
+        node.apply_patch(&before.diff(&after).unwrap()).unwrap();
+        assert_eq!(node.content, after.content);
+        assert_eq!(node.parent_id, after.parent_id);
+        assert_eq!(node.metadata, after.metadata);
+        assert_eq!(node.id, before.id);
+    }
+
+    #[test]
+    fn node_patch_rejects_unpatchable_fields_without_changes() {
+        let mut node = Node::new("text".to_string(), json!("Hello"));
+        let original = node.clone();
+        let result = node.apply_patch(&patch(json!([
+            {"op": "replace", "path": "/content", "value": "Changed"},
+            {"op": "replace", "path": "/id", "value": "other"}
+        ])));
+        assert!(result.is_err());
+        assert_eq!(node.content, original.content);
+        assert_eq!(node.id, original.id);
+    }
+
+    #[test]
+    fn node_patch_bumps_revision_only_on_change() {
+        let mut node = Node::new("text".to_string(), json!("Hello"));
+        let read = node.revision;
+
+        node.apply_patch(&patch(
+            json!([{"op": "test", "path": "/content", "value": "Hello"}]),
+        ))
+        .unwrap();
+        assert_eq!(node.revision, read);
+
+        node.apply_patch(&patch(
+            json!([{"op": "replace", "path": "/content", "value": "Hi"}]),
+        ))
+        .unwrap();
+        assert_eq!(node.revision, read + 1);
+        assert!(node.check_revision(read).is_err());
+    }
+
+    #[test]
+    fn merge_patch_bumps_revision_only_on_change() {
+        let mut node = Node::new("text".to_string(), json!({"title": "Draft"}));
+        let read = node.revision;
+
+        node.merge_patch(&json!({"content": {"title": "Draft"}}))
+            .unwrap();
+        assert_eq!(node.revision, read);
+
+        node.merge_patch(&json!({"content": {"done": true}}))
+            .unwrap();
+        assert_eq!(node.revision, read + 1);
+        assert!(node.check_revision(read).is_err());
+    }
+}
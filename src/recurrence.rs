@@ -0,0 +1,912 @@
+//! Recurrence rules
+//!
+//! [`RRule`] is the subset of the RFC 5545 `RRULE` property used by recurring
+//! tasks and journal templates: `FREQ`, `INTERVAL`, `BYDAY`, `BYMONTHDAY`,
+//! `COUNT` and `UNTIL`. Rules are day-based and expanded from a start date
+//! (`DTSTART`), with weeks starting on Monday. Only occurrences on or after the
+//! start date are produced, and `COUNT` counts those.
+//!
+//! `BYDAY` takes plain weekdays (`MO`, `TU`, ...) and, in monthly and yearly
+//! rules, ordinal forms such as `1MO` (first Monday) or `-1FR` (last Friday) of
+//! the month or year. Rules serialize as their `RRULE` string, e.g.
+//! `FREQ=WEEKLY;BYDAY=MO,WE`.
+
+use crate::calendar::CalendarLayout;
+use crate::hierarchy::{HierarchyChangeSet, Staging};
+use crate::{Node, NodeSpaceResult, ValidationError};
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Periods without a single occurrence after which expansion gives up, so
+/// rules that can never match (e.g. `FREQ=DAILY;INTERVAL=7;BYDAY=TU` from a
+/// Monday) terminate
+const MAX_EMPTY_PERIODS: u32 = 10_000;
+
+const EXAMPLES: &[&str] = &[
+    "FREQ=DAILY",
+    "FREQ=WEEKLY;BYDAY=MO",
+    "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;COUNT=10",
+    "FREQ=MONTHLY;BYMONTHDAY=-1",
+    "FREQ=MONTHLY;BYDAY=-1FR",
+    "FREQ=YEARLY;UNTIL=20301231",
+];
+
+const WEEKDAY_CODES: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+/// How often a rule repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        }
+    }
+}
+
+/// One `BYDAY` entry: every `weekday` of the period, or only the `ordinal`th
+/// one when set, counting from the end of the period if negative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ByDay {
+    pub weekday: Weekday,
+    pub ordinal: Option<i32>,
+}
+
+impl ByDay {
+    /// Every `weekday` of the period
+    pub fn every(weekday: Weekday) -> Self {
+        Self {
+            weekday,
+            ordinal: None,
+        }
+    }
+
+    /// The `ordinal`th `weekday` of the month or year, e.g. `-1` for the last
+    pub fn nth(ordinal: i32, weekday: Weekday) -> Self {
+        Self {
+            weekday,
+            ordinal: Some(ordinal),
+        }
+    }
+}
+
+impl From<Weekday> for ByDay {
+    fn from(weekday: Weekday) -> Self {
+        Self::every(weekday)
+    }
+}
+
+impl fmt::Display for ByDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ordinal) = self.ordinal {
+            write!(f, "{}", ordinal)?;
+        }
+        f.write_str(weekday_code(self.weekday))
+    }
+}
+
+/// A recurrence rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RRule {
+    pub freq: Frequency,
+    /// Repeat every `interval` periods, at least 1
+    pub interval: u32,
+    /// Weekdays the rule falls on
+    pub by_day: Vec<ByDay>,
+    /// Days of the month, 1 to 31 or -1 (last day) to -31
+    pub by_month_day: Vec<i32>,
+    /// Total number of occurrences
+    pub count: Option<u32>,
+    /// Last date an occurrence may fall on (inclusive)
+    pub until: Option<NaiveDate>,
+}
+
+impl RRule {
+    /// Create an unbounded rule repeating every period
+    pub fn new(freq: Frequency) -> Self {
+        Self {
+            freq,
+            interval: 1,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            count: None,
+            until: None,
+        }
+    }
+
+    /// Repeat every day
+    pub fn daily() -> Self {
+        Self::new(Frequency::Daily)
+    }
+
+    /// Repeat every week on the given weekdays
+    pub fn weekly_on(days: &[Weekday]) -> Self {
+        Self::new(Frequency::Weekly).with_by_day(days)
+    }
+
+    /// Repeat every month on the given day of the month
+    pub fn monthly_on(day: i32) -> Self {
+        Self::new(Frequency::Monthly).with_by_month_day(&[day])
+    }
+
+    /// Repeat every `interval` periods; 0 fails [`RRule::validate`]
+    pub fn with_interval(mut self, interval: u32) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_by_day(mut self, days: &[Weekday]) -> Self {
+        self.by_day = days.iter().copied().map(ByDay::every).collect();
+        self
+    }
+
+    /// Add the `ordinal`th `weekday` of the month or year to `BYDAY`
+    pub fn with_nth_weekday(mut self, ordinal: i32, weekday: Weekday) -> Self {
+        self.by_day.push(ByDay::nth(ordinal, weekday));
+        self
+    }
+
+    pub fn with_by_month_day(mut self, days: &[i32]) -> Self {
+        self.by_month_day = days.to_vec();
+        self
+    }
+
+    pub fn with_count(mut self, count: u32) -> Self {
+        self.count = Some(count);
+        self.until = None;
+        self
+    }
+
+    pub fn with_until(mut self, until: NaiveDate) -> Self {
+        self.until = Some(until);
+        self.count = None;
+        self
+    }
+
+    /// Parse an `RRULE` value, with or without the `RRULE:` prefix
+    pub fn parse(value: &str) -> NodeSpaceResult<Self> {
+        Ok(value.parse()?)
+    }
+
+    /// Check if the rule ends, through `COUNT` or `UNTIL`
+    pub fn is_finite(&self) -> bool {
+        self.count.is_some() || self.until.is_some()
+    }
+
+    /// Check the rule parts against each other
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.interval == 0 {
+            return Err(ValidationError::out_of_range(
+                "rrule.INTERVAL",
+                "0",
+                "1",
+                &u32::MAX.to_string(),
+            ));
+        }
+        if let Some(day) = self
+            .by_month_day
+            .iter()
+            .find(|day| **day == 0 || !(-31..=31).contains(*day))
+        {
+            return Err(ValidationError::out_of_range(
+                "rrule.BYMONTHDAY",
+                &day.to_string(),
+                "-31",
+                "31",
+            ));
+        }
+        if let Some(ordinal) = self
+            .by_day
+            .iter()
+            .filter_map(|day| day.ordinal)
+            .find(|ordinal| *ordinal == 0 || !(-53..=53).contains(ordinal))
+        {
+            return Err(ValidationError::out_of_range(
+                "rrule.BYDAY",
+                &ordinal.to_string(),
+                "-53",
+                "53",
+            ));
+        }
+        if matches!(self.freq, Frequency::Daily | Frequency::Weekly)
+            && self.by_day.iter().any(|day| day.ordinal.is_some())
+        {
+            return Err(invalid_part(
+                "rrule.BYDAY",
+                "plain weekdays unless FREQ is MONTHLY or YEARLY",
+                &self.to_string(),
+            ));
+        }
+        if self.count.is_some() && self.until.is_some() {
+            return Err(invalid_part(
+                "rrule",
+                "either COUNT or UNTIL, not both",
+                &self.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Occurrences starting from `dtstart`, in order
+    ///
+    /// Unbounded rules yield occurrences until the end of the supported date
+    /// range; combine with `take_while` or use [`RRule::between`]. A rule that
+    /// fails [`RRule::validate`] (such as one built with `interval: 0`) yields
+    /// nothing.
+    pub fn occurrences(&self, dtstart: NaiveDate) -> impl Iterator<Item = NaiveDate> + '_ {
+        Occurrences {
+            rule: self,
+            dtstart,
+            period: 0,
+            empty_periods: 0,
+            pending: Vec::new(),
+            emitted: 0,
+            done: self.validate().is_err(),
+        }
+    }
+
+    /// Occurrences from `from` to `to` inclusive
+    pub fn between(&self, dtstart: NaiveDate, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        self.occurrences(dtstart)
+            .skip_while(|date| *date < from)
+            .take_while(|date| *date <= to)
+            .collect()
+    }
+
+    /// First occurrence strictly after `after`
+    pub fn next_after(&self, dtstart: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        self.occurrences(dtstart).find(|date| *date > after)
+    }
+
+    /// First day of the `index`th period from `dtstart`
+    fn period_start(&self, dtstart: NaiveDate, index: u32) -> Option<NaiveDate> {
+        let steps = index.checked_mul(self.interval)?;
+        match self.freq {
+            Frequency::Daily => dtstart.checked_add_days(Days::new(steps as u64)),
+            Frequency::Weekly => {
+                let monday = dtstart - Days::new(dtstart.weekday().num_days_from_monday() as u64);
+                monday.checked_add_days(Days::new(steps as u64 * 7))
+            }
+            Frequency::Monthly => dtstart.with_day(1)?.checked_add_months(Months::new(steps)),
+            Frequency::Yearly => NaiveDate::from_ymd_opt(dtstart.year(), 1, 1)?
+                .checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    }
+
+    /// Candidate dates within the period starting at `start`, sorted
+    fn candidates(&self, dtstart: NaiveDate, start: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates: Vec<NaiveDate> = match self.freq {
+            Frequency::Daily => vec![start],
+            Frequency::Weekly if self.by_day.is_empty() => {
+                vec![start + Days::new(dtstart.weekday().num_days_from_monday() as u64)]
+            }
+            Frequency::Weekly => self
+                .by_day
+                .iter()
+                .map(|day| start + Days::new(day.weekday.num_days_from_monday() as u64))
+                .collect(),
+            Frequency::Monthly => self.days_in_month(dtstart, start.year(), start.month()),
+            Frequency::Yearly if self.by_day.is_empty() && self.by_month_day.is_empty() => {
+                NaiveDate::from_ymd_opt(start.year(), dtstart.month(), dtstart.day())
+                    .into_iter()
+                    .collect()
+            }
+            Frequency::Yearly => (1..=12)
+                .flat_map(|month| self.days_in_month(dtstart, start.year(), month))
+                .collect(),
+        };
+        dates.retain(|date| self.matches_filters(*date));
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    /// Dates a monthly (or yearly, per month) rule expands to
+    fn days_in_month(&self, dtstart: NaiveDate, year: i32, month: u32) -> Vec<NaiveDate> {
+        if !self.by_month_day.is_empty() {
+            let length = month_length(year, month);
+            self.by_month_day
+                .iter()
+                .filter_map(|day| {
+                    let day = if *day < 0 { length + 1 + *day } else { *day };
+                    NaiveDate::from_ymd_opt(year, month, u32::try_from(day).ok()?)
+                })
+                .collect()
+        } else if !self.by_day.is_empty() {
+            (1..=month_length(year, month))
+                .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day as u32))
+                .collect()
+        } else {
+            NaiveDate::from_ymd_opt(year, month, dtstart.day())
+                .into_iter()
+                .collect()
+        }
+    }
+
+    fn matches_filters(&self, date: NaiveDate) -> bool {
+        let length = month_length(date.year(), date.month());
+        let day = date.day() as i32;
+        (self.by_day.is_empty()
+            || self.by_day.iter().any(|by_day| {
+                by_day.weekday == date.weekday()
+                    && by_day
+                        .ordinal
+                        .is_none_or(|ordinal| self.weekday_ordinals(date).contains(&ordinal))
+            }))
+            && (self.by_month_day.is_empty()
+                || self
+                    .by_month_day
+                    .iter()
+                    .any(|d| *d == day || *d == day - length - 1))
+    }
+
+    /// Which occurrence of its weekday `date` is within the month (or the year
+    /// for yearly rules), counted from the start and from the end
+    fn weekday_ordinals(&self, date: NaiveDate) -> [i32; 2] {
+        let (index, length) = match self.freq {
+            Frequency::Yearly => {
+                let length = if NaiveDate::from_ymd_opt(date.year(), 2, 29).is_some() {
+                    366
+                } else {
+                    365
+                };
+                (date.ordinal0() as i32, length)
+            }
+            _ => (date.day0() as i32, month_length(date.year(), date.month())),
+        };
+        [index / 7 + 1, -((length - 1 - index) / 7 + 1)]
+    }
+}
+
+struct Occurrences<'a> {
+    rule: &'a RRule,
+    dtstart: NaiveDate,
+    period: u32,
+    empty_periods: u32,
+    /// Candidates of the current period, in reverse order
+    pending: Vec<NaiveDate>,
+    emitted: u32,
+    done: bool,
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = NaiveDate;
+
+    fn next(&mut self) -> Option<NaiveDate> {
+        while !self.done {
+            if self.rule.count.is_some_and(|count| self.emitted >= count) {
+                break;
+            }
+            if let Some(date) = self.pending.pop() {
+                if self.rule.until.is_some_and(|until| date > until) {
+                    break;
+                }
+                if date < self.dtstart {
+                    continue;
+                }
+                self.emitted += 1;
+                return Some(date);
+            }
+
+            let Some(start) = self.rule.period_start(self.dtstart, self.period) else {
+                break;
+            };
+            if self.rule.until.is_some_and(|until| start > until)
+                || self.empty_periods >= MAX_EMPTY_PERIODS
+            {
+                break;
+            }
+            self.period += 1;
+            self.pending = self.rule.candidates(self.dtstart, start);
+            self.pending.reverse();
+            if self.pending.iter().any(|date| *date >= self.dtstart) {
+                self.empty_periods = 0;
+            } else {
+                self.empty_periods += 1;
+            }
+        }
+        self.done = true;
+        None
+    }
+}
+
+fn month_length(year: i32, month: u32) -> i32 {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).expect("valid month");
+    let next = first
+        .checked_add_months(Months::new(1))
+        .unwrap_or(NaiveDate::MAX);
+    (next - first).num_days() as i32
+}
+
+fn invalid_part(field: &str, expected: &str, actual: &str) -> ValidationError {
+    ValidationError::InvalidFormat {
+        field: field.to_string(),
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+        examples: EXAMPLES.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    WEEKDAY_CODES
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, day)| *day)
+}
+
+/// `MO`, or with an ordinal such as `2TU`, `+2TU` or `-1FR`
+fn parse_by_day(value: &str) -> Option<ByDay> {
+    let split = value.len().checked_sub(2)?;
+    let weekday = parse_weekday(value.get(split..)?)?;
+    let ordinal = match value.get(..split)? {
+        "" => None,
+        ordinal => Some(ordinal.parse().ok()?),
+    };
+    Some(ByDay { weekday, ordinal })
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    WEEKDAY_CODES[day.num_days_from_monday() as usize].0
+}
+
+/// `YYYYMMDD` or an RFC 5545 date-time (`YYYYMMDDTHHMMSS[Z]`), keeping the date
+fn parse_until(value: &str) -> Option<NaiveDate> {
+    let date = value.split(['T', 't']).next()?;
+    NaiveDate::parse_from_str(date, "%Y%m%d").ok()
+}
+
+impl fmt::Display for RRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.freq.as_str())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self.by_day.iter().map(|d| d.to_string()).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days: Vec<String> = self.by_month_day.iter().map(|d| d.to_string()).collect();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RRule {
+    type Err = ValidationError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.trim();
+        let body = trimmed
+            .get(..6)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+            .map_or(trimmed, |_| &trimmed[6..]);
+
+        let mut freq = None;
+        let mut rule = RRule::new(Frequency::Daily);
+        for part in body.split(';').filter(|part| !part.is_empty()) {
+            let Some((key, val)) = part.split_once('=') else {
+                return Err(invalid_part("rrule", "KEY=VALUE parts", part));
+            };
+            let field = format!("rrule.{}", key.to_ascii_uppercase());
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match val.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => {
+                            return Err(invalid_part(
+                                &field,
+                                "DAILY, WEEKLY, MONTHLY or YEARLY",
+                                val,
+                            ))
+                        }
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = val
+                        .parse()
+                        .map_err(|_| invalid_part(&field, "a positive integer", val))?
+                }
+                "BYDAY" => {
+                    rule.by_day = val
+                        .split(',')
+                        .map(|code| {
+                            parse_by_day(code).ok_or_else(|| {
+                                invalid_part(
+                                    &field,
+                                    "weekday codes MO, TU, WE, TH, FR, SA, SU, optionally \
+                                     preceded by an ordinal such as 1MO or -1FR",
+                                    val,
+                                )
+                            })
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = val
+                        .split(',')
+                        .map(|day| {
+                            day.parse()
+                                .map_err(|_| invalid_part(&field, "days from -31 to 31", val))
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        val.parse()
+                            .map_err(|_| invalid_part(&field, "a non-negative integer", val))?,
+                    )
+                }
+                "UNTIL" => {
+                    rule.until = Some(
+                        parse_until(val).ok_or_else(|| invalid_part(&field, "YYYYMMDD", val))?,
+                    )
+                }
+                _ => {
+                    return Err(invalid_part(
+                        "rrule",
+                        "FREQ, INTERVAL, BYDAY, BYMONTHDAY, COUNT or UNTIL",
+                        part,
+                    ))
+                }
+            }
+        }
+
+        rule.freq = freq.ok_or_else(|| ValidationError::RequiredFieldMissing {
+            field: "rrule.FREQ".to_string(),
+            context: format!("recurrence rule {:?}", value),
+            suggestion: Some(
+                "Start the rule with FREQ=DAILY, WEEKLY, MONTHLY or YEARLY".to_string(),
+            ),
+        })?;
+        rule.validate()?;
+        Ok(rule)
+    }
+}
+
+impl TryFrom<String> for RRule {
+    type Error = ValidationError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RRule> for String {
+    fn from(rule: RRule) -> Self {
+        rule.to_string()
+    }
+}
+
+/// Build or look up the calendar chain for every occurrence of a rule between
+/// `from` and `to` inclusive
+///
+/// Like [`crate::calendar::ensure_date_range`], only nodes that had to be
+/// created or restored are returned. Invalid rules are rejected.
+pub fn ensure_recurrence_dates(
+    nodes: &[Node],
+    rule: &RRule,
+    dtstart: NaiveDate,
+    from: NaiveDate,
+    to: NaiveDate,
+    layout: CalendarLayout,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    rule.validate()?;
+    let mut staging = Staging::new(nodes);
+    for date in rule.between(dtstart, from, to) {
+        crate::calendar::ensure_path(&mut staging, date, layout)?;
+    }
+    Ok(staging.finish(Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn first(rule: &str, dtstart: NaiveDate, n: usize) -> Vec<NaiveDate> {
+        RRule::parse(rule)
+            .unwrap()
+            .occurrences(dtstart)
+            .take(n)
+            .collect()
+    }
+
+    #[test]
+    fn weekly_by_day_expands_within_each_week() {
+        let dates = first("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH", date(2025, 6, 4), 4);
+        assert_eq!(
+            dates,
+            vec![
+                date(2025, 6, 5),
+                date(2025, 6, 17),
+                date(2025, 6, 19),
+                date(2025, 7, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn by_day_with_ordinals_picks_nth_weekday_of_month() {
+        let dates = first("FREQ=MONTHLY;BYDAY=1MO,-1FR", date(2025, 1, 1), 6);
+        assert_eq!(
+            dates,
+            vec![
+                date(2025, 1, 6),
+                date(2025, 1, 31),
+                date(2025, 2, 3),
+                date(2025, 2, 28),
+                date(2025, 3, 3),
+                date(2025, 3, 28)
+            ]
+        );
+
+        let rule = RRule::new(Frequency::Monthly).with_nth_weekday(2, Weekday::Tue);
+        assert_eq!(rule, RRule::parse("FREQ=MONTHLY;BYDAY=+2TU").unwrap());
+        assert_eq!(
+            rule.next_after(date(2025, 6, 1), date(2025, 6, 10)),
+            Some(date(2025, 7, 8))
+        );
+    }
+
+    #[test]
+    fn by_day_with_ordinals_in_yearly_rules_counts_within_the_year() {
+        let dates = first("FREQ=YEARLY;BYDAY=1MO,-1SU", date(2025, 1, 1), 4);
+        assert_eq!(
+            dates,
+            vec![
+                date(2025, 1, 6),
+                date(2025, 12, 28),
+                date(2026, 1, 5),
+                date(2026, 12, 27)
+            ]
+        );
+    }
+
+    #[test]
+    fn by_day_with_ordinals_combines_with_by_month_day() {
+        // Friday the 13th
+        let dates = first("FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13", date(2025, 1, 1), 2);
+        assert_eq!(dates, vec![date(2025, 6, 13), date(2026, 2, 13)]);
+
+        // Months ending on a Friday
+        let dates = first("FREQ=MONTHLY;BYDAY=-1FR;BYMONTHDAY=-1", date(2025, 1, 1), 3);
+        assert_eq!(
+            dates,
+            vec![date(2025, 1, 31), date(2025, 2, 28), date(2025, 10, 31)]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_by_day_ordinals() {
+        for rule in [
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=DAILY;BYDAY=-1FR",
+            "FREQ=MONTHLY;BYDAY=0MO",
+            "FREQ=YEARLY;BYDAY=54MO",
+            "FREQ=MONTHLY;BYDAY=1XX",
+            "FREQ=MONTHLY;BYDAY=AMO",
+            "FREQ=MONTHLY;BYDAY=M",
+        ] {
+            assert!(RRule::parse(rule).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn count_limits_occurrences_from_dtstart() {
+        let rule = RRule::parse("FREQ=WEEKLY;BYDAY=MO,FR;COUNT=3").unwrap();
+        // The Monday before a Wednesday start is not produced or counted
+        let dates: Vec<_> = rule.occurrences(date(2025, 6, 4)).collect();
+        assert_eq!(
+            dates,
+            vec![date(2025, 6, 6), date(2025, 6, 9), date(2025, 6, 13)]
+        );
+        assert!(rule.is_finite());
+    }
+
+    #[test]
+    fn until_is_inclusive() {
+        let rule = RRule::parse("FREQ=DAILY;INTERVAL=3;UNTIL=20250607").unwrap();
+        let dates: Vec<_> = rule.occurrences(date(2025, 6, 1)).collect();
+        assert_eq!(
+            dates,
+            vec![date(2025, 6, 1), date(2025, 6, 4), date(2025, 6, 7)]
+        );
+
+        let rule = RRule::parse("FREQ=DAILY;UNTIL=20250602T235959Z").unwrap();
+        assert_eq!(rule.until, Some(date(2025, 6, 2)));
+        assert_eq!(rule.occurrences(date(2025, 6, 1)).count(), 2);
+    }
+
+    #[test]
+    fn count_and_until_are_exclusive() {
+        let error = RRule::parse("FREQ=DAILY;COUNT=3;UNTIL=20250607").unwrap_err();
+        assert!(matches!(
+            error,
+            crate::NodeSpaceError::Validation(ValidationError::InvalidFormat { .. })
+        ));
+
+        let rule = RRule::daily().with_count(3).with_until(date(2025, 6, 7));
+        assert_eq!(rule.count, None);
+        assert!(rule.validate().is_ok());
+    }
+
+    #[test]
+    fn negative_by_month_day_follows_month_length() {
+        let dates = first("FREQ=MONTHLY;BYMONTHDAY=-1", date(2024, 1, 15), 4);
+        assert_eq!(
+            dates,
+            vec![
+                date(2024, 1, 31),
+                date(2024, 2, 29),
+                date(2024, 3, 31),
+                date(2024, 4, 30)
+            ]
+        );
+        let dates = first("FREQ=MONTHLY;BYMONTHDAY=-1", date(2025, 2, 1), 1);
+        assert_eq!(dates, vec![date(2025, 2, 28)]);
+    }
+
+    #[test]
+    fn by_month_day_skips_months_without_that_day() {
+        let dates = first("FREQ=MONTHLY;BYMONTHDAY=31", date(2025, 1, 1), 3);
+        assert_eq!(
+            dates,
+            vec![date(2025, 1, 31), date(2025, 3, 31), date(2025, 5, 31)]
+        );
+        assert!(RRule::parse("FREQ=MONTHLY;BYMONTHDAY=32").is_err());
+        assert!(RRule::parse("FREQ=MONTHLY;BYMONTHDAY=0").is_err());
+    }
+
+    #[test]
+    fn parse_serialize_round_trip() {
+        for rule in [
+            "FREQ=DAILY",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;COUNT=10",
+            "FREQ=MONTHLY;BYDAY=1MO,-1FR",
+            "FREQ=MONTHLY;BYMONTHDAY=1,-1;UNTIL=20301231",
+            "FREQ=YEARLY;INTERVAL=4",
+        ] {
+            let parsed = RRule::parse(rule).unwrap();
+            assert_eq!(parsed.to_string(), rule);
+            assert_eq!(RRule::parse(&parsed.to_string()).unwrap(), parsed);
+        }
+
+        let parsed = RRule::parse("RRULE:freq=weekly;interval=1;byday=mo").unwrap();
+        assert_eq!(parsed.to_string(), "FREQ=WEEKLY;BYDAY=MO");
+
+        let json = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(json, serde_json::json!("FREQ=WEEKLY;BYDAY=MO"));
+        assert_eq!(serde_json::from_value::<RRule>(json).unwrap(), parsed);
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert!(matches!(
+            RRule::parse("INTERVAL=2"),
+            Err(crate::NodeSpaceError::Validation(
+                ValidationError::RequiredFieldMissing { .. }
+            ))
+        ));
+        for rule in [
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;BYHOUR=9",
+            "FREQ=DAILY;COUNT",
+        ] {
+            assert!(RRule::parse(rule).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn invalid_rules_yield_no_occurrences() {
+        let start = date(2025, 1, 1);
+        let zero = RRule::daily().with_interval(0);
+        assert!(zero.validate().is_err());
+        assert_eq!(zero.occurrences(start).next(), None);
+        assert!(zero.between(start, start, date(2025, 12, 31)).is_empty());
+        assert_eq!(zero.next_after(start, start), None);
+
+        let literal = RRule {
+            interval: 0,
+            ..RRule::weekly_on(&[Weekday::Mon])
+        };
+        assert_eq!(literal.next_after(start, start), None);
+
+        let bad_day = RRule::new(Frequency::Monthly).with_by_month_day(&[32]);
+        assert_eq!(bad_day.occurrences(start).count(), 0);
+    }
+
+    #[test]
+    fn ensure_recurrence_dates_builds_each_occurrence_path() {
+        use crate::NodeId;
+
+        let rule = RRule::weekly_on(&[Weekday::Mon]);
+        let from = date(2025, 1, 20);
+        let to = date(2025, 2, 10);
+        let changes = ensure_recurrence_dates(
+            &[],
+            &rule,
+            date(2025, 1, 1),
+            from,
+            to,
+            CalendarLayout::Monthly,
+        )
+        .unwrap();
+
+        let mut ids: Vec<&str> = changes.updated.iter().map(|n| n.id.as_str()).collect();
+        ids.sort();
+        let mut expected: Vec<String> = [
+            NodeId::for_year(2025),
+            NodeId::for_month(2025, 1),
+            NodeId::for_month(2025, 2),
+            NodeId::for_date(date(2025, 1, 20)),
+            NodeId::for_date(date(2025, 1, 27)),
+            NodeId::for_date(date(2025, 2, 3)),
+            NodeId::for_date(date(2025, 2, 10)),
+        ]
+        .iter()
+        .map(|id| id.to_string())
+        .collect();
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        let mut nodes = Vec::new();
+        changes.apply_to(&mut nodes);
+        assert!(crate::validate_hierarchy(&nodes).is_empty());
+        let again = ensure_recurrence_dates(
+            &nodes,
+            &rule,
+            date(2025, 1, 1),
+            from,
+            to,
+            CalendarLayout::Monthly,
+        )
+        .unwrap();
+        assert!(again.is_empty());
+
+        let zero = rule.clone().with_interval(0);
+        assert!(ensure_recurrence_dates(
+            &nodes,
+            &zero,
+            date(2025, 1, 1),
+            from,
+            to,
+            CalendarLayout::Monthly
+        )
+        .is_err());
+    }
+}
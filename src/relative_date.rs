@@ -0,0 +1,380 @@
+//! Natural-language relative date parsing
+//!
+//! Resolves quick-capture expressions such as "tomorrow", "next friday",
+//! "in 3 days", "2 weeks ago", "july 4" or "2025-07-01" against a reference date.
+//! Matching is case-insensitive and ignores commas.
+//!
+//! Weekday rules: a bare weekday or "this friday" is the next such day on or
+//! after the reference date, "next friday" is the Friday of the following
+//! (Monday-based) week and "last friday" is the most recent Friday before the
+//! reference date. "next week" / "next month" / "next year" resolve to the first
+//! day of that period.
+
+use crate::timezone::NodeTimezone;
+use crate::{DateNodeMetadata, NodeId, NodeSpaceResult, ValidationError};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc, Weekday};
+
+/// Expressions shown to the user when parsing fails
+const EXAMPLES: &[&str] = &[
+    "today",
+    "tomorrow",
+    "next friday",
+    "in 3 days",
+    "2 weeks ago",
+    "july 4",
+    "2025-07-01",
+];
+
+/// Parse a date expression relative to `reference`
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use nodespace_core_types::parse_relative_date;
+///
+/// let wednesday = NaiveDate::from_ymd_opt(2025, 7, 2).unwrap();
+/// let friday = NaiveDate::from_ymd_opt(2025, 7, 4).unwrap();
+/// assert_eq!(parse_relative_date("Friday", wednesday).unwrap(), friday);
+/// assert_eq!(parse_relative_date("in 2 days", wednesday).unwrap(), friday);
+/// assert!(parse_relative_date("someday", wednesday).is_err());
+/// ```
+pub fn parse_relative_date(input: &str, reference: NaiveDate) -> NodeSpaceResult<NaiveDate> {
+    let normalized = input.trim().to_lowercase().replace(',', " ");
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+    parse_words(&words, reference).ok_or_else(|| {
+        ValidationError::InvalidFormat {
+            field: "date".to_string(),
+            expected: "a date or relative date expression".to_string(),
+            actual: input.to_string(),
+            examples: EXAMPLES.iter().map(|e| e.to_string()).collect(),
+        }
+        .into()
+    })
+}
+
+/// Parse a date expression relative to the local date of `now` in `timezone`
+pub fn resolve_relative_date(
+    input: &str,
+    now: DateTime<Utc>,
+    timezone: &str,
+) -> NodeSpaceResult<NaiveDate> {
+    let reference = NodeTimezone::parse(timezone)?.local_date(now);
+    parse_relative_date(input, reference)
+}
+
+impl DateNodeMetadata {
+    /// Create DateNodeMetadata for a date expression such as "next friday"
+    pub fn from_relative(input: &str, now: DateTime<Utc>, timezone: &str) -> NodeSpaceResult<Self> {
+        let date = resolve_relative_date(input, now, timezone)?;
+        let zone = NodeTimezone::parse(timezone)?;
+        Ok(Self::with_timezone(date, &zone.to_string()))
+    }
+}
+
+impl NodeId {
+    /// ID of the day node a date expression such as "tomorrow" refers to
+    pub fn for_relative_date(
+        input: &str,
+        now: DateTime<Utc>,
+        timezone: &str,
+    ) -> NodeSpaceResult<Self> {
+        Ok(Self::for_date(resolve_relative_date(input, now, timezone)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+fn parse_words(words: &[&str], reference: NaiveDate) -> Option<NaiveDate> {
+    match words {
+        ["today"] | ["now"] => Some(reference),
+        ["tomorrow"] | ["tmrw"] | ["tmr"] => shift(reference, Unit::Day, 1),
+        ["yesterday"] => shift(reference, Unit::Day, -1),
+        ["the", "day", "after", "tomorrow"] | ["day", "after", "tomorrow"] => {
+            shift(reference, Unit::Day, 2)
+        }
+        ["the", "day", "before", "yesterday"] | ["day", "before", "yesterday"] => {
+            shift(reference, Unit::Day, -2)
+        }
+        [word] | ["this", word] if weekday(word).is_some() => {
+            Some(on_or_after(reference, weekday(word)?))
+        }
+        ["next", word] if weekday(word).is_some() => {
+            let this_week = start_of_week(reference) + Days::new(days_from_monday(weekday(word)?));
+            shift(this_week, Unit::Week, 1)
+        }
+        ["last", word] if weekday(word).is_some() => {
+            let yesterday = reference.pred_opt()?;
+            let back = (7 + yesterday.weekday().num_days_from_monday()
+                - weekday(word)?.num_days_from_monday())
+                % 7;
+            yesterday.checked_sub_days(Days::new(back as u64))
+        }
+        ["next", unit] => start_of(reference, unit_of(unit)?, 1),
+        ["last", unit] => start_of(reference, unit_of(unit)?, -1),
+        ["this", unit] => start_of(reference, unit_of(unit)?, 0),
+        ["end", "of", "the", unit] | ["end", "of", unit] => {
+            let unit = unit_of(unit)?;
+            start_of(reference, unit, 1)?.pred_opt()
+        }
+        ["in", count, unit] | ["in", count, unit, "time"] => {
+            shift(reference, unit_of(unit)?, number(count)?)
+        }
+        [count, unit, "ago"] => shift(reference, unit_of(unit)?, number(count)?.checked_neg()?),
+        [count, unit, "from", "now"] | [count, unit, "later"] => {
+            shift(reference, unit_of(unit)?, number(count)?)
+        }
+        [single] => iso_date(single),
+        _ => month_day(words, reference),
+    }
+}
+
+fn iso_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(word, "%Y/%m/%d"))
+        .ok()
+}
+
+/// "july 4", "4 july", "jul 4th 2026", "4th of july"
+///
+/// Without a year, the next occurrence on or after the reference date is used.
+fn month_day(words: &[&str], reference: NaiveDate) -> Option<NaiveDate> {
+    let words: Vec<&str> = words.iter().copied().filter(|w| *w != "of").collect();
+    let (first, second, year) = match words.as_slice() {
+        [first, second] => (*first, *second, None),
+        [first, second, year] => (*first, *second, Some(year.parse().ok()?)),
+        _ => return None,
+    };
+    let (month, day) = match (month(first), day(second)) {
+        (Some(month), Some(day)) => (month, day),
+        _ => (month(second)?, day(first)?),
+    };
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+        None => {
+            let this_year = NaiveDate::from_ymd_opt(reference.year(), month, day);
+            match this_year {
+                Some(date) if date >= reference => Some(date),
+                _ => NaiveDate::from_ymd_opt(reference.year() + 1, month, day),
+            }
+        }
+    }
+}
+
+fn shift(date: NaiveDate, unit: Unit, count: i64) -> Option<NaiveDate> {
+    let magnitude = count.unsigned_abs();
+    match unit {
+        Unit::Day | Unit::Week => {
+            let days = Days::new(if matches!(unit, Unit::Week) {
+                magnitude.checked_mul(7)?
+            } else {
+                magnitude
+            });
+            if count >= 0 {
+                date.checked_add_days(days)
+            } else {
+                date.checked_sub_days(days)
+            }
+        }
+        Unit::Month | Unit::Year => {
+            let months = if matches!(unit, Unit::Year) {
+                magnitude.checked_mul(12)?
+            } else {
+                magnitude
+            };
+            let months = Months::new(u32::try_from(months).ok()?);
+            if count >= 0 {
+                date.checked_add_months(months)
+            } else {
+                date.checked_sub_months(months)
+            }
+        }
+    }
+}
+
+/// First day of the week, month or year `offset` periods away from `date`
+fn start_of(date: NaiveDate, unit: Unit, offset: i64) -> Option<NaiveDate> {
+    let start = match unit {
+        Unit::Day => date,
+        Unit::Week => start_of_week(date),
+        Unit::Month => date.with_day(1)?,
+        Unit::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1)?,
+    };
+    shift(start, unit, offset)
+}
+
+fn start_of_week(date: NaiveDate) -> NaiveDate {
+    date - Days::new(days_from_monday(date.weekday()))
+}
+
+fn days_from_monday(weekday: Weekday) -> u64 {
+    weekday.num_days_from_monday() as u64
+}
+
+fn on_or_after(date: NaiveDate, target: Weekday) -> NaiveDate {
+    let ahead = (7 + target.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7;
+    date + Days::new(ahead as u64)
+}
+
+fn unit_of(word: &str) -> Option<Unit> {
+    match word {
+        "day" | "days" => Some(Unit::Day),
+        "week" | "weeks" | "wk" | "wks" => Some(Unit::Week),
+        "month" | "months" | "mo" | "mos" => Some(Unit::Month),
+        "year" | "years" | "yr" | "yrs" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+fn number(word: &str) -> Option<i64> {
+    const WORDS: [&str; 13] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve",
+    ];
+    match word {
+        "a" | "an" => Some(1),
+        _ => word
+            .parse()
+            .ok()
+            .or_else(|| WORDS.iter().position(|w| *w == word).map(|n| n as i64)),
+    }
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thur" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    MONTHS
+        .iter()
+        .position(|m| *m == word || (word.len() >= 3 && m.starts_with(word)))
+        .map(|index| index as u32 + 1)
+}
+
+/// Day of month, allowing ordinal suffixes ("1st", "22nd")
+fn day(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let day: u32 = digits.parse().ok()?;
+    (1..=31).contains(&day).then_some(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday
+    fn reference() -> NaiveDate {
+        date(2025, 7, 2)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn parse(input: &str) -> NaiveDate {
+        parse_relative_date(input, reference()).unwrap()
+    }
+
+    #[test]
+    fn named_days() {
+        assert_eq!(parse("today"), reference());
+        assert_eq!(parse("Tomorrow"), date(2025, 7, 3));
+        assert_eq!(parse("yesterday"), date(2025, 7, 1));
+        assert_eq!(parse("the day after tomorrow"), date(2025, 7, 4));
+        assert_eq!(parse("day before yesterday"), date(2025, 6, 30));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(parse("wednesday"), reference());
+        assert_eq!(parse("this friday"), date(2025, 7, 4));
+        assert_eq!(parse("monday"), date(2025, 7, 7));
+        assert_eq!(parse("next friday"), date(2025, 7, 11));
+        assert_eq!(parse("next monday"), date(2025, 7, 7));
+        assert_eq!(parse("last wednesday"), date(2025, 6, 25));
+        assert_eq!(parse("last tue"), date(2025, 7, 1));
+    }
+
+    #[test]
+    fn periods() {
+        assert_eq!(parse("next week"), date(2025, 7, 7));
+        assert_eq!(parse("last week"), date(2025, 6, 23));
+        assert_eq!(parse("this month"), date(2025, 7, 1));
+        assert_eq!(parse("next month"), date(2025, 8, 1));
+        assert_eq!(parse("next year"), date(2026, 1, 1));
+        assert_eq!(parse("end of the month"), date(2025, 7, 31));
+        assert_eq!(parse("end of week"), date(2025, 7, 6));
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse("in 3 days"), date(2025, 7, 5));
+        assert_eq!(parse("in a week"), date(2025, 7, 9));
+        assert_eq!(parse("2 weeks ago"), date(2025, 6, 18));
+        assert_eq!(parse("three months from now"), date(2025, 10, 2));
+        assert_eq!(parse("1 year later"), date(2026, 7, 2));
+        assert_eq!(parse("0 days ago"), reference());
+        assert_eq!(parse("-1 days ago"), date(2025, 7, 3));
+    }
+
+    #[test]
+    fn absolute_dates() {
+        assert_eq!(parse("2025-12-31"), date(2025, 12, 31));
+        assert_eq!(parse("2025/01/05"), date(2025, 1, 5));
+        assert_eq!(parse("july 4"), date(2025, 7, 4));
+        assert_eq!(parse("4th of July"), date(2025, 7, 4));
+        assert_eq!(parse("jun 30"), date(2026, 6, 30));
+        assert_eq!(parse("1 jul, 2026"), date(2026, 7, 1));
+    }
+
+    #[test]
+    fn rejects_unknown_or_out_of_range_input() {
+        for input in [
+            "",
+            "someday",
+            "in 3 fortnights",
+            "february 30 2025",
+            "-9223372036854775808 days ago",
+            "9223372036854775807 days ago",
+            "in 9223372036854775807 years",
+            "9223372036854775807 weeks ago",
+            "99999999999999999999 days ago",
+        ] {
+            let error = parse_relative_date(input, reference()).unwrap_err();
+            assert!(
+                matches!(
+                    error,
+                    crate::NodeSpaceError::Validation(ValidationError::InvalidFormat { .. })
+                ),
+                "{input}: {error:?}"
+            );
+        }
+    }
+}
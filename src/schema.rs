@@ -0,0 +1,338 @@
+//! Per-type content schemas
+//!
+//! `Node.r#type` is a free string and `content` is arbitrary JSON. A
+//! [`SchemaRegistry`] maps node types to a [`ContentSchema`] describing the
+//! expected shape of `content` (required fields, field types and enum values) so
+//! malformed nodes are rejected before they reach storage.
+//!
+//! Field names may use dots to address nested objects, e.g. `date_metadata.date`.
+
+use crate::{Node, NodeSpaceResult, ValidationError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Expected JSON type of a content value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+    /// String holding an ISO date (`YYYY-MM-DD`)
+    Date,
+    /// String holding an RFC 3339 timestamp
+    DateTime,
+    /// String restricted to a fixed set of values
+    Enum(Vec<String>),
+    /// Any of several types
+    OneOf(Vec<FieldType>),
+    /// Any JSON value, including null
+    Any,
+}
+
+impl FieldType {
+    /// Check a value against this type, describing the mismatch on failure
+    fn check(&self, value: &Value) -> Result<(), String> {
+        let matches = match self {
+            FieldType::String => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::Array => value.is_array(),
+            FieldType::Object => value.is_object(),
+            FieldType::Date => value
+                .as_str()
+                .is_some_and(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()),
+            FieldType::DateTime => value
+                .as_str()
+                .is_some_and(|s| chrono::DateTime::parse_from_rfc3339(s).is_ok()),
+            FieldType::Enum(allowed) => value
+                .as_str()
+                .is_some_and(|s| allowed.iter().any(|a| a == s)),
+            FieldType::OneOf(types) => types.iter().any(|t| t.check(value).is_ok()),
+            FieldType::Any => true,
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(format!("expected {}, got {}", self, value))
+        }
+    }
+}
+
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldType::String => write!(f, "string"),
+            FieldType::Number => write!(f, "number"),
+            FieldType::Integer => write!(f, "integer"),
+            FieldType::Boolean => write!(f, "boolean"),
+            FieldType::Array => write!(f, "array"),
+            FieldType::Object => write!(f, "object"),
+            FieldType::Date => write!(f, "date (YYYY-MM-DD)"),
+            FieldType::DateTime => write!(f, "RFC 3339 timestamp"),
+            FieldType::Enum(allowed) => write!(f, "one of [{}]", allowed.join(", ")),
+            FieldType::OneOf(types) => {
+                let names: Vec<String> = types.iter().map(ToString::to_string).collect();
+                write!(f, "{}", names.join(" or "))
+            }
+            FieldType::Any => write!(f, "any value"),
+        }
+    }
+}
+
+/// A single field of a content schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    /// Field name, dotted for nested objects
+    pub name: String,
+    pub field_type: FieldType,
+    pub required: bool,
+}
+
+/// Expected shape of `content` for one node type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentSchema {
+    pub node_type: String,
+    pub version: String,
+    /// Type of the content value itself
+    pub content_type: FieldType,
+    pub fields: Vec<FieldSchema>,
+}
+
+impl ContentSchema {
+    /// Create a schema for object content with no fields yet
+    pub fn new(node_type: &str, version: &str) -> Self {
+        Self {
+            node_type: node_type.to_string(),
+            version: version.to_string(),
+            content_type: FieldType::Object,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Set the expected type of the content value itself
+    pub fn with_content_type(mut self, content_type: FieldType) -> Self {
+        self.content_type = content_type;
+        self
+    }
+
+    /// Add a field that must be present
+    pub fn with_required_field(mut self, name: &str, field_type: FieldType) -> Self {
+        self.fields.push(FieldSchema {
+            name: name.to_string(),
+            field_type,
+            required: true,
+        });
+        self
+    }
+
+    /// Add a field that is checked only when present and not null
+    pub fn with_optional_field(mut self, name: &str, field_type: FieldType) -> Self {
+        self.fields.push(FieldSchema {
+            name: name.to_string(),
+            field_type,
+            required: false,
+        });
+        self
+    }
+
+    /// List every way a content value violates this schema
+    ///
+    /// Fields describe object content and are not checked when the content type
+    /// allows another shape and the content has it.
+    pub fn violations(&self, content: &Value) -> Vec<String> {
+        if let Err(reason) = self.content_type.check(content) {
+            return vec![format!("content: {}", reason)];
+        }
+        if !content.is_object() {
+            return Vec::new();
+        }
+
+        let mut violations = Vec::new();
+        for field in &self.fields {
+            match lookup(content, &field.name) {
+                None | Some(Value::Null) if field.required => {
+                    violations.push(format!("content.{}: required field missing", field.name));
+                }
+                None | Some(Value::Null) => {}
+                Some(value) => {
+                    if let Err(reason) = field.field_type.check(value) {
+                        violations.push(format!("content.{}: {}", field.name, reason));
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// Content schemas keyed by node type
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+    schemas: HashMap<String, ContentSchema>,
+}
+
+impl SchemaRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with schemas for the built-in `text`, `task` and `date`
+    /// nodes
+    ///
+    /// Text content is either a string or an object with a `content` string.
+    pub fn with_builtin_schemas() -> Self {
+        let enum_of =
+            |values: &[&str]| FieldType::Enum(values.iter().map(|v| v.to_string()).collect());
+
+        let mut registry = Self::new();
+        registry.register(
+            ContentSchema::new("text", "1.0")
+                .with_content_type(FieldType::OneOf(vec![FieldType::String, FieldType::Object]))
+                .with_required_field("content", FieldType::String),
+        );
+        registry.register(
+            ContentSchema::new("task", "1.0")
+                .with_required_field("title", FieldType::String)
+                .with_optional_field(
+                    "status",
+                    enum_of(&["todo", "in-progress", "done", "cancelled"]),
+                )
+                .with_optional_field("priority", enum_of(&["low", "medium", "high", "urgent"]))
+                .with_optional_field("due_date", FieldType::Date)
+                .with_optional_field("scheduled_date", FieldType::Date)
+                .with_optional_field("completed_at", FieldType::DateTime)
+                .with_optional_field("assignee", FieldType::String)
+                .with_optional_field("recurrence", FieldType::String),
+        );
+        registry.register(
+            ContentSchema::new("date", "1.0")
+                .with_required_field("type", enum_of(&["date"]))
+                .with_required_field("content", FieldType::String)
+                .with_required_field("date_metadata", FieldType::Object)
+                .with_required_field("date_metadata.date", FieldType::Date)
+                .with_required_field("date_metadata.timezone", FieldType::String)
+                .with_required_field("date_metadata.display_format", FieldType::String)
+                .with_required_field("date_metadata.created_by_navigation", FieldType::Boolean)
+                .with_optional_field("date_metadata.locale", FieldType::String)
+                .with_optional_field("period", enum_of(&["year", "month", "week", "day"])),
+        );
+        registry
+    }
+
+    /// Register a schema, returning the one it replaces for the same node type
+    pub fn register(&mut self, schema: ContentSchema) -> Option<ContentSchema> {
+        self.schemas.insert(schema.node_type.clone(), schema)
+    }
+
+    /// Remove the schema for a node type
+    pub fn unregister(&mut self, node_type: &str) -> Option<ContentSchema> {
+        self.schemas.remove(node_type)
+    }
+
+    /// Get the schema registered for a node type
+    pub fn get(&self, node_type: &str) -> Option<&ContentSchema> {
+        self.schemas.get(node_type)
+    }
+
+    /// Check if a schema is registered for a node type
+    pub fn contains(&self, node_type: &str) -> bool {
+        self.schemas.contains_key(node_type)
+    }
+
+    /// Validate a node's content against the schema for its type
+    ///
+    /// Nodes whose type has no registered schema are accepted. Every violation is
+    /// reported in a single [`ValidationError::SchemaValidationFailed`].
+    ///
+    /// ```
+    /// use nodespace_core_types::{Node, SchemaRegistry, ValidationError};
+    ///
+    /// let registry = SchemaRegistry::with_builtin_schemas();
+    /// assert!(registry.validate(&Node::new("text".to_string(), "hi".into())).is_ok());
+    ///
+    /// let broken = Node::new("date".to_string(), serde_json::json!({"type": "date"}));
+    /// let error = registry.validate(&broken).unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     nodespace_core_types::NodeSpaceError::Validation(
+    ///         ValidationError::SchemaValidationFailed { .. }
+    ///     )
+    /// ));
+    /// ```
+    pub fn validate(&self, node: &Node) -> NodeSpaceResult<()> {
+        let Some(schema) = self.get(&node.r#type) else {
+            return Ok(());
+        };
+        let violations = schema.violations(&node.content);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::SchemaValidationFailed {
+                schema_path: schema.node_type.clone(),
+                violations,
+                schema_version: schema.version.clone(),
+            }
+            .into())
+        }
+    }
+}
+
+/// Resolve a dotted field name inside a JSON object
+fn lookup<'a>(content: &'a Value, name: &str) -> Option<&'a Value> {
+    name.split('.')
+        .try_fold(content, |value, segment| value.get(segment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(node_type: &str, content: Value) -> NodeSpaceResult<()> {
+        SchemaRegistry::with_builtin_schemas().validate(&Node::new(node_type.to_string(), content))
+    }
+
+    #[test]
+    fn text_accepts_string_and_object_content() {
+        assert!(validate("text", json!("Hello world")).is_ok());
+        assert!(validate("text", json!({"content": "Hello world"})).is_ok());
+        assert!(validate("text", json!({"content": "Hi", "tags": ["a"]})).is_ok());
+    }
+
+    #[test]
+    fn text_rejects_other_shapes() {
+        for content in [
+            json!(42),
+            json!(null),
+            json!(["Hello"]),
+            json!({}),
+            json!({"content": 42}),
+        ] {
+            assert!(validate("text", content.clone()).is_err(), "{content}");
+        }
+    }
+
+    #[test]
+    fn violations_list_every_failing_field() {
+        let schema = SchemaRegistry::with_builtin_schemas();
+        let violations = schema
+            .get("task")
+            .unwrap()
+            .violations(&json!({"status": "later", "due_date": "tomorrow"}));
+        assert_eq!(violations.len(), 3, "{violations:?}");
+    }
+
+    #[test]
+    fn one_of_display() {
+        let field_type = FieldType::OneOf(vec![FieldType::String, FieldType::Object]);
+        assert_eq!(field_type.to_string(), "string or object");
+        assert!(field_type.check(&json!(1)).is_err());
+    }
+}
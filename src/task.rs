@@ -0,0 +1,364 @@
+//! Task nodes
+//!
+//! [`TaskContent`] is the typed content of `task` nodes: a title plus status,
+//! priority, due and scheduled dates, completion time, assignee and an optional
+//! recurrence rule. Dates are calendar days so they line up with the date-node
+//! hierarchy.
+
+use crate::content::NodeContent;
+use crate::recurrence::RRule;
+use crate::{Node, NodeSpaceResult, ValidationError};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Workflow state of a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskStatus {
+    #[default]
+    Todo,
+    InProgress,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    /// Check if the task still needs work
+    pub fn is_open(&self) -> bool {
+        matches!(self, TaskStatus::Todo | TaskStatus::InProgress)
+    }
+}
+
+/// Task priority
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Urgent,
+}
+
+/// Typed content of a `task` node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskContent {
+    pub title: String,
+    #[serde(default)]
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub priority: TaskPriority,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub scheduled_date: Option<NaiveDate>,
+    /// Set when the task is marked done
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Repeats the task; anchored on the due date, else the scheduled date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<RRule>,
+}
+
+impl NodeContent for TaskContent {
+    const NODE_TYPE: &'static str = "task";
+}
+
+impl TaskContent {
+    /// Create an open task with default priority
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            status: TaskStatus::Todo,
+            priority: TaskPriority::default(),
+            due_date: None,
+            scheduled_date: None,
+            completed_at: None,
+            assignee: None,
+            recurrence: None,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: TaskPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_due_date(mut self, due_date: NaiveDate) -> Self {
+        self.due_date = Some(due_date);
+        self
+    }
+
+    pub fn with_scheduled_date(mut self, scheduled_date: NaiveDate) -> Self {
+        self.scheduled_date = Some(scheduled_date);
+        self
+    }
+
+    pub fn with_assignee(mut self, assignee: &str) -> Self {
+        self.assignee = Some(assignee.to_string());
+        self
+    }
+
+    pub fn with_recurrence(mut self, recurrence: RRule) -> Self {
+        self.recurrence = Some(recurrence);
+        self
+    }
+
+    /// Date the recurrence rule is anchored on
+    pub fn recurrence_anchor(&self) -> Option<NaiveDate> {
+        self.due_date.or(self.scheduled_date)
+    }
+
+    /// The next instance of a recurring task
+    ///
+    /// The copy is open, keeps title, priority and assignee, and has its due and
+    /// scheduled dates moved to the next occurrence after the current anchor.
+    /// A `COUNT` limit is reduced by the instance consumed. Returns `None` for
+    /// non-recurring tasks and when the rule has no further occurrences.
+    pub fn next_occurrence(&self) -> Option<Self> {
+        let rule = self.recurrence.as_ref()?;
+        let anchor = self.recurrence_anchor()?;
+        let next = rule.next_after(anchor, anchor)?;
+        let shift = next - anchor;
+
+        let mut recurrence = rule.clone();
+        if let Some(count) = recurrence.count.as_mut() {
+            *count = count.saturating_sub(1);
+        }
+        Some(Self {
+            status: TaskStatus::Todo,
+            due_date: self.due_date.map(|date| date + shift),
+            scheduled_date: self.scheduled_date.map(|date| date + shift),
+            completed_at: None,
+            recurrence: Some(recurrence),
+            ..self.clone()
+        })
+    }
+
+    /// Mark the task done at the given time
+    pub fn complete(&mut self, at: DateTime<Utc>) {
+        self.status = TaskStatus::Done;
+        self.completed_at = Some(at);
+    }
+
+    /// Move the task to a new status, keeping `completed_at` consistent
+    pub fn set_status(&mut self, status: TaskStatus, now: DateTime<Utc>) {
+        match status {
+            TaskStatus::Done => self.complete(now),
+            other => {
+                self.status = other;
+                self.completed_at = None;
+            }
+        }
+    }
+
+    /// Check if the task is open and its due date is before the day of `now` (UTC)
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.is_overdue_as_of(now.date_naive())
+    }
+
+    /// Check if the task is open and its due date is before `date`
+    pub fn is_overdue_as_of(&self, date: NaiveDate) -> bool {
+        self.status.is_open() && self.due_date.is_some_and(|due| due < date)
+    }
+
+    /// Check if the task is overdue as of the day represented by a date node
+    ///
+    /// Returns false when `date_node` is not a date node.
+    pub fn is_overdue_on(&self, date_node: &Node) -> bool {
+        date_node
+            .get_date()
+            .is_some_and(|date| self.is_overdue_as_of(date))
+    }
+
+    /// Check if the task is due on the day represented by a date node
+    pub fn is_due_on(&self, date_node: &Node) -> bool {
+        self.due_date.is_some() && self.due_date == date_node.get_date()
+    }
+
+    /// Validate the task fields against each other
+    pub fn validate(&self) -> NodeSpaceResult<()> {
+        if self.title.trim().is_empty() {
+            return Err(ValidationError::required_field("title", "TaskContent").into());
+        }
+
+        match (self.status, self.completed_at) {
+            (TaskStatus::Done, None) => {
+                return Err(ValidationError::RequiredFieldMissing {
+                    field: "completed_at".to_string(),
+                    context: "task with status done".to_string(),
+                    suggestion: Some("Use TaskContent::complete() to mark a task done".to_string()),
+                }
+                .into());
+            }
+            (status, Some(completed_at)) if status != TaskStatus::Done => {
+                return Err(ValidationError::BusinessRuleViolation {
+                    rule: "Only done tasks have a completion time".to_string(),
+                    context: serde_json::json!({
+                        "status": status,
+                        "completed_at": completed_at,
+                    }),
+                    resolution_steps: vec![
+                        "Clear completed_at, or set the status to done".to_string()
+                    ],
+                }
+                .into());
+            }
+            _ => {}
+        }
+
+        if let Some(recurrence) = &self.recurrence {
+            recurrence.validate()?;
+            if self.recurrence_anchor().is_none() {
+                return Err(ValidationError::RequiredFieldMissing {
+                    field: "due_date".to_string(),
+                    context: "recurring task".to_string(),
+                    suggestion: Some(
+                        "Set a due or scheduled date to anchor the recurrence".to_string(),
+                    ),
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Convert to a generic `task` Node
+    pub fn to_node(&self) -> NodeSpaceResult<Node> {
+        self.validate()?;
+        Node::from_typed(self.clone())
+    }
+
+    /// Create TaskContent from a generic Node
+    pub fn from_node(node: &Node) -> NodeSpaceResult<Self> {
+        let task: Self = node.content_as()?;
+        task.validate()?;
+        Ok(task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeSpaceError;
+    use chrono::{TimeZone, Weekday};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn noon(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn validate_checks_title_and_completion() {
+        assert!(TaskContent::new("Ship it").validate().is_ok());
+        assert!(TaskContent::new("  ").validate().is_err());
+
+        let mut task = TaskContent::new("Ship it");
+        task.status = TaskStatus::Done;
+        assert!(matches!(
+            task.validate(),
+            Err(NodeSpaceError::Validation(ValidationError::RequiredFieldMissing { field, .. }))
+                if field == "completed_at"
+        ));
+
+        task.complete(noon(2025, 3, 1));
+        assert!(task.validate().is_ok());
+
+        task.status = TaskStatus::InProgress;
+        assert!(matches!(
+            task.validate(),
+            Err(NodeSpaceError::Validation(
+                ValidationError::BusinessRuleViolation { .. }
+            ))
+        ));
+
+        task.set_status(TaskStatus::Todo, noon(2025, 3, 2));
+        assert_eq!(task.completed_at, None);
+        assert!(task.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_requires_anchor_for_recurrence() {
+        let task = TaskContent::new("Water plants").with_recurrence(RRule::daily());
+        assert!(task.validate().is_err());
+        assert!(task
+            .with_scheduled_date(date(2025, 3, 1))
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn due_and_overdue_against_date_nodes() {
+        let today = Node::new_date_node(date(2025, 3, 10));
+        let task = TaskContent::new("File taxes").with_due_date(date(2025, 3, 10));
+        assert!(task.is_due_on(&today));
+        assert!(!task.is_overdue_on(&today));
+
+        let tomorrow = Node::new_date_node(date(2025, 3, 11));
+        assert!(!task.is_due_on(&tomorrow));
+        assert!(task.is_overdue_on(&tomorrow));
+        assert!(task.is_overdue(noon(2025, 3, 11)));
+
+        let mut done = task.clone();
+        done.complete(noon(2025, 3, 10));
+        assert!(!done.is_overdue_on(&tomorrow));
+
+        let text = Node::new("text".to_string(), serde_json::json!("2025-03-11"));
+        assert!(!task.is_overdue_on(&text));
+        assert!(!TaskContent::new("Someday").is_due_on(&today));
+    }
+
+    #[test]
+    fn next_occurrence_shifts_dates_and_consumes_count() {
+        let mut task = TaskContent::new("Team sync")
+            .with_priority(TaskPriority::High)
+            .with_assignee("sam")
+            .with_due_date(date(2025, 3, 3))
+            .with_scheduled_date(date(2025, 3, 1))
+            .with_recurrence(RRule::weekly_on(&[Weekday::Mon]).with_count(2));
+        task.complete(noon(2025, 3, 3));
+
+        let next = task.next_occurrence().unwrap();
+        assert_eq!(next.due_date, Some(date(2025, 3, 10)));
+        assert_eq!(next.scheduled_date, Some(date(2025, 3, 8)));
+        assert_eq!(next.status, TaskStatus::Todo);
+        assert_eq!(next.completed_at, None);
+        assert_eq!(next.priority, TaskPriority::High);
+        assert_eq!(next.assignee.as_deref(), Some("sam"));
+        assert_eq!(next.recurrence.as_ref().unwrap().count, Some(1));
+
+        assert_eq!(next.next_occurrence(), None);
+        assert_eq!(TaskContent::new("Once").next_occurrence(), None);
+    }
+
+    #[test]
+    fn node_round_trip() {
+        let mut task = TaskContent::new("Write release notes")
+            .with_priority(TaskPriority::Urgent)
+            .with_due_date(date(2025, 4, 1))
+            .with_recurrence(RRule::monthly_on(1));
+        task.complete(noon(2025, 4, 1));
+
+        let node = task.to_node().unwrap();
+        assert_eq!(node.r#type, "task");
+        assert_eq!(node.content["status"], "done");
+        assert_eq!(node.content["priority"], "urgent");
+        assert_eq!(TaskContent::from_node(&node).unwrap(), task);
+
+        let mut invalid = node.clone();
+        invalid.content["completed_at"] = serde_json::Value::Null;
+        assert!(TaskContent::from_node(&invalid).is_err());
+        assert!(TaskContent::new("").to_node().is_err());
+
+        let text = Node::new("text".to_string(), node.content.clone());
+        assert!(TaskContent::from_node(&text).is_err());
+    }
+}
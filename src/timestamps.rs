@@ -0,0 +1,193 @@
+//! Lenient timestamp parsing for stored nodes
+//!
+//! `Node` timestamps are typed `DateTime<Utc>` values and serialize as RFC 3339.
+//! Older payloads stored them as free-form strings, so deserialization also
+//! accepts a few legacy forms; anything else is rejected instead of being
+//! carried along as an unparseable string.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Deserializer};
+
+/// Naive formats interpreted as UTC
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f UTC",
+];
+
+/// Parse a timestamp in any accepted format
+///
+/// Accepts RFC 3339, RFC 2822, naive `YYYY-MM-DD[T ]HH:MM:SS[.fff]` (taken as
+/// UTC), a bare `YYYY-MM-DD` (midnight UTC) and Unix epoch seconds or
+/// milliseconds.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    if let Ok(parsed) = DateTime::parse_from_rfc2822(value) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    if let Some(parsed) = NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    {
+        return Some(Utc.from_utc_datetime(&parsed));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?));
+    }
+    value.parse::<i64>().ok().and_then(from_epoch)
+}
+
+/// Interpret an integer as epoch seconds, or milliseconds when it is too large
+/// to be a plausible number of seconds
+fn from_epoch(value: i64) -> Option<DateTime<Utc>> {
+    const MAX_SECONDS: i64 = 100_000_000_000;
+    if value.abs() < MAX_SECONDS {
+        DateTime::from_timestamp(value, 0)
+    } else {
+        DateTime::from_timestamp_millis(value)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTimestamp {
+    Text(String),
+    Epoch(i64),
+}
+
+impl RawTimestamp {
+    fn parse<E: serde::de::Error>(self) -> Result<DateTime<Utc>, E> {
+        match self {
+            RawTimestamp::Text(text) => parse_timestamp(&text)
+                .ok_or_else(|| E::custom(format!("invalid timestamp: {:?}", text))),
+            RawTimestamp::Epoch(value) => from_epoch(value)
+                .ok_or_else(|| E::custom(format!("timestamp out of range: {}", value))),
+        }
+    }
+}
+
+/// Deserialize a required timestamp leniently
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    RawTimestamp::deserialize(deserializer)?.parse()
+}
+
+/// Deserialize an optional timestamp leniently (`null` is `None`)
+pub fn deserialize_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    Option::<RawTimestamp>::deserialize(deserializer)?
+        .map(RawTimestamp::parse)
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, s).unwrap()
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Stamped {
+        #[serde(deserialize_with = "deserialize")]
+        at: DateTime<Utc>,
+        #[serde(default, deserialize_with = "deserialize_option")]
+        until: Option<DateTime<Utc>>,
+    }
+
+    #[test]
+    fn parses_rfc3339_and_rfc2822() {
+        let expected = utc(2025, 6, 30, 9, 15, 0);
+        assert_eq!(parse_timestamp("2025-06-30T09:15:00Z"), Some(expected));
+        assert_eq!(parse_timestamp("2025-06-30T11:15:00+02:00"), Some(expected));
+        assert_eq!(
+            parse_timestamp(" 2025-06-30T09:15:00+00:00 "),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_timestamp("Mon, 30 Jun 2025 09:15:00 +0000"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_timestamp("Mon, 30 Jun 2025 05:15:00 -0400"),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn parses_naive_datetimes_and_dates_as_utc() {
+        let expected = utc(2025, 6, 30, 9, 15, 0);
+        assert_eq!(parse_timestamp("2025-06-30T09:15:00"), Some(expected));
+        assert_eq!(parse_timestamp("2025-06-30 09:15:00"), Some(expected));
+        assert_eq!(parse_timestamp("2025-06-30 09:15:00 UTC"), Some(expected));
+        assert_eq!(
+            parse_timestamp("2025-06-30 09:15:00.250"),
+            Some(expected + chrono::Duration::milliseconds(250))
+        );
+        assert_eq!(
+            parse_timestamp("2025-06-30"),
+            Some(utc(2025, 6, 30, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn parses_epoch_seconds_and_milliseconds() {
+        let expected = utc(2025, 6, 30, 9, 15, 0);
+        let seconds = expected.timestamp();
+        assert_eq!(parse_timestamp(&seconds.to_string()), Some(expected));
+        assert_eq!(
+            parse_timestamp(&(seconds * 1000).to_string()),
+            Some(expected)
+        );
+        assert_eq!(parse_timestamp("0"), Some(DateTime::UNIX_EPOCH));
+        // Just below the cut-off is still seconds (year 5138)
+        assert_eq!(
+            parse_timestamp("99999999999").map(|t| t.timestamp()),
+            Some(99_999_999_999)
+        );
+        assert_eq!(
+            parse_timestamp("100000000000").map(|t| t.timestamp_millis()),
+            Some(100_000_000_000)
+        );
+    }
+
+    #[test]
+    fn rejects_unparseable_values() {
+        for value in ["", "yesterday", "2025-13-01", "30/06/2025", "12ab"] {
+            assert_eq!(parse_timestamp(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn deserializes_strings_and_numbers() {
+        let stamped: Stamped =
+            serde_json::from_str(r#"{"at": "2025-06-30", "until": 1751274900}"#).unwrap();
+        assert_eq!(stamped.at, utc(2025, 6, 30, 0, 0, 0));
+        assert_eq!(stamped.until, Some(utc(2025, 6, 30, 9, 15, 0)));
+
+        let stamped: Stamped =
+            serde_json::from_str(r#"{"at": 1751274900000, "until": null}"#).unwrap();
+        assert_eq!(stamped.at, utc(2025, 6, 30, 9, 15, 0));
+        assert_eq!(stamped.until, None);
+
+        let stamped: Stamped = serde_json::from_str(r#"{"at": "1751274900"}"#).unwrap();
+        assert_eq!(stamped.at, utc(2025, 6, 30, 9, 15, 0));
+        assert_eq!(stamped.until, None);
+    }
+
+    #[test]
+    fn deserialize_rejects_invalid_values() {
+        let error = serde_json::from_str::<Stamped>(r#"{"at": "soon"}"#).unwrap_err();
+        assert!(error.to_string().contains("invalid timestamp"), "{error}");
+        assert!(serde_json::from_str::<Stamped>(r#"{"at": null}"#).is_err());
+        assert!(serde_json::from_str::<Stamped>(r#"{"at": true}"#).is_err());
+        assert!(
+            serde_json::from_str::<Stamped>(r#"{"at": "2025-06-30", "until": "later"}"#).is_err()
+        );
+    }
+}
@@ -0,0 +1,425 @@
+//! Timezone resolution for date nodes
+//!
+//! `DateNodeMetadata.timezone` accepts a fixed UTC offset (`+02:00`, `-0530`,
+//! `UTC+2`) or a named zone from a small built-in table. Named zones carry their
+//! standard offset and daylight-saving rule, which is enough to decide which
+//! calendar day an instant falls on without a full tz database.
+
+use crate::calendar::CalendarLayout;
+use crate::hierarchy::HierarchyChangeSet;
+use crate::{DateNodeMetadata, Node, NodeId, NodeSpaceResult, ValidationError};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone, Utc, Weekday};
+use std::fmt;
+use std::str::FromStr;
+
+/// Daylight-saving rule of a named zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DstRule {
+    None,
+    /// Second Sunday of March to first Sunday of November, at 02:00 local time
+    UnitedStates,
+    /// Last Sunday of March to last Sunday of October, at 01:00 UTC
+    EuropeanUnion,
+    /// First Sunday of October to first Sunday of April, at 02:00 standard time
+    SouthEastAustralia,
+}
+
+/// Name, standard offset in minutes and DST rule of a built-in zone
+#[derive(Debug, PartialEq, Eq)]
+struct NamedZone(&'static str, i32, DstRule);
+
+/// Built-in named zones
+const NAMED_ZONES: &[NamedZone] = &[
+    NamedZone("UTC", 0, DstRule::None),
+    NamedZone("Europe/London", 0, DstRule::EuropeanUnion),
+    NamedZone("Europe/Dublin", 0, DstRule::EuropeanUnion),
+    NamedZone("Europe/Lisbon", 0, DstRule::EuropeanUnion),
+    NamedZone("Europe/Berlin", 60, DstRule::EuropeanUnion),
+    NamedZone("Europe/Paris", 60, DstRule::EuropeanUnion),
+    NamedZone("Europe/Madrid", 60, DstRule::EuropeanUnion),
+    NamedZone("Europe/Rome", 60, DstRule::EuropeanUnion),
+    NamedZone("Europe/Amsterdam", 60, DstRule::EuropeanUnion),
+    NamedZone("Europe/Athens", 120, DstRule::EuropeanUnion),
+    NamedZone("America/New_York", -300, DstRule::UnitedStates),
+    NamedZone("America/Chicago", -360, DstRule::UnitedStates),
+    NamedZone("America/Denver", -420, DstRule::UnitedStates),
+    NamedZone("America/Phoenix", -420, DstRule::None),
+    NamedZone("America/Los_Angeles", -480, DstRule::UnitedStates),
+    NamedZone("America/Anchorage", -540, DstRule::UnitedStates),
+    NamedZone("Pacific/Honolulu", -600, DstRule::None),
+    NamedZone("America/Sao_Paulo", -180, DstRule::None),
+    NamedZone("Asia/Dubai", 240, DstRule::None),
+    NamedZone("Asia/Kolkata", 330, DstRule::None),
+    NamedZone("Asia/Singapore", 480, DstRule::None),
+    NamedZone("Asia/Shanghai", 480, DstRule::None),
+    NamedZone("Asia/Tokyo", 540, DstRule::None),
+    NamedZone("Australia/Sydney", 600, DstRule::SouthEastAustralia),
+    NamedZone("Australia/Melbourne", 600, DstRule::SouthEastAustralia),
+];
+
+/// Aliases resolved to a named zone
+const ZONE_ALIASES: &[(&str, &str)] = &[
+    ("Z", "UTC"),
+    ("GMT", "UTC"),
+    ("Etc/UTC", "UTC"),
+    ("Asia/Calcutta", "Asia/Kolkata"),
+];
+
+/// A parsed timezone: a fixed offset or a named zone with DST
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeTimezone {
+    kind: ZoneKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ZoneKind {
+    Fixed(FixedOffset),
+    Named(&'static NamedZone),
+}
+
+impl NodeTimezone {
+    /// Parse a fixed offset or a named zone
+    pub fn parse(value: &str) -> NodeSpaceResult<Self> {
+        Ok(value.parse()?)
+    }
+
+    /// Names of the built-in zones
+    pub fn known_zones() -> impl Iterator<Item = &'static str> {
+        NAMED_ZONES.iter().map(|zone| zone.0)
+    }
+
+    /// Check if this is a fixed offset rather than a named zone
+    pub fn is_fixed_offset(&self) -> bool {
+        matches!(self.kind, ZoneKind::Fixed(_))
+    }
+
+    /// UTC offset in effect at an instant
+    pub fn offset_at(&self, instant: DateTime<Utc>) -> FixedOffset {
+        match self.kind {
+            ZoneKind::Fixed(offset) => offset,
+            ZoneKind::Named(&NamedZone(_, standard_offset_minutes, rule)) => {
+                let daylight = in_daylight_time(rule, standard_offset_minutes, instant);
+                let minutes = standard_offset_minutes + if daylight { 60 } else { 0 };
+                FixedOffset::east_opt(minutes * 60).expect("built-in offsets are in range")
+            }
+        }
+    }
+
+    /// Local calendar date of an instant in this zone
+    pub fn local_date(&self, instant: DateTime<Utc>) -> NaiveDate {
+        instant.with_timezone(&self.offset_at(instant)).date_naive()
+    }
+}
+
+impl fmt::Display for NodeTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ZoneKind::Fixed(offset) => write!(f, "{}", offset),
+            ZoneKind::Named(zone) => write!(f, "{}", zone.0),
+        }
+    }
+}
+
+impl FromStr for NodeTimezone {
+    type Err = ValidationError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.trim();
+        let name = ZONE_ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(trimmed))
+            .map(|(_, name)| *name)
+            .unwrap_or(trimmed);
+
+        if let Some(zone) = NAMED_ZONES.iter().find(|z| z.0.eq_ignore_ascii_case(name)) {
+            return Ok(NodeTimezone {
+                kind: ZoneKind::Named(zone),
+            });
+        }
+
+        parse_fixed_offset(trimmed)
+            .map(|offset| NodeTimezone {
+                kind: ZoneKind::Fixed(offset),
+            })
+            .ok_or_else(|| ValidationError::InvalidFormat {
+                field: "timezone".to_string(),
+                expected: "a UTC offset or a known zone name".to_string(),
+                actual: value.to_string(),
+                examples: vec![
+                    "UTC".to_string(),
+                    "+02:00".to_string(),
+                    "UTC-05:30".to_string(),
+                    "Europe/Berlin".to_string(),
+                    "America/New_York".to_string(),
+                ],
+            })
+    }
+}
+
+/// Parse `+HH:MM`, `+HHMM`, `+HH`, optionally prefixed with `UTC` or `GMT`
+fn parse_fixed_offset(value: &str) -> Option<FixedOffset> {
+    let upper = value.to_ascii_uppercase();
+    let rest = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+
+    let (sign, digits) = match rest.as_bytes().first()? {
+        b'+' => (1, &rest[1..]),
+        b'-' => (-1, &rest[1..]),
+        _ => return None,
+    };
+    // Reject anything else up front so the byte split below stays on a char boundary
+    if !digits.bytes().all(|b| b.is_ascii_digit() || b == b':') {
+        return None;
+    }
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if digits.len() == 4 => digits.split_at(2),
+        None => (digits, "0"),
+    };
+    if hours.is_empty()
+        || hours.len() > 2
+        || !hours
+            .bytes()
+            .chain(minutes.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes >= 60 || (hours == 14 && minutes > 0) {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+fn in_daylight_time(rule: DstRule, standard_offset_minutes: i32, instant: DateTime<Utc>) -> bool {
+    let year = instant.year();
+    // Local wall-clock time expressed in UTC
+    let local_to_utc = |date: NaiveDate, hour: u32, offset_minutes: i32| {
+        let local = date.and_hms_opt(hour, 0, 0).expect("valid wall-clock hour");
+        Utc.from_utc_datetime(&(local - chrono::Duration::minutes(offset_minutes as i64)))
+    };
+    let daylight_offset = standard_offset_minutes + 60;
+
+    match rule {
+        DstRule::None => false,
+        DstRule::UnitedStates => {
+            let start = local_to_utc(nth_weekday(year, 3, 2), 2, standard_offset_minutes);
+            let end = local_to_utc(nth_weekday(year, 11, 1), 2, daylight_offset);
+            instant >= start && instant < end
+        }
+        DstRule::EuropeanUnion => {
+            let start = local_to_utc(last_sunday(year, 3), 1, 0);
+            let end = local_to_utc(last_sunday(year, 10), 1, 0);
+            instant >= start && instant < end
+        }
+        DstRule::SouthEastAustralia => {
+            let end = local_to_utc(nth_weekday(year, 4, 1), 3, daylight_offset);
+            let start = local_to_utc(nth_weekday(year, 10, 1), 2, standard_offset_minutes);
+            instant < end || instant >= start
+        }
+    }
+}
+
+/// The `n`th Sunday of a month
+fn nth_weekday(year: i32, month: u32, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, n)
+        .expect("every month has at least four Sundays")
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, 5)
+        .unwrap_or_else(|| nth_weekday(year, month, 4))
+}
+
+impl NodeId {
+    /// ID of the day node an instant falls on in a timezone
+    pub fn for_instant(instant: DateTime<Utc>, timezone: &str) -> NodeSpaceResult<Self> {
+        Ok(Self::for_date(
+            NodeTimezone::parse(timezone)?.local_date(instant),
+        ))
+    }
+}
+
+impl DateNodeMetadata {
+    /// Parse the stored timezone
+    pub fn parse_timezone(&self) -> NodeSpaceResult<NodeTimezone> {
+        NodeTimezone::parse(&self.timezone)
+    }
+
+    /// Create DateNodeMetadata for the local day an instant falls on
+    ///
+    /// The timezone is stored in its canonical form (e.g. `Europe/Berlin` or
+    /// `+05:30`).
+    pub fn for_instant(instant: DateTime<Utc>, timezone: &str) -> NodeSpaceResult<Self> {
+        let zone = NodeTimezone::parse(timezone)?;
+        Ok(Self::with_timezone(
+            zone.local_date(instant),
+            &zone.to_string(),
+        ))
+    }
+}
+
+impl Node {
+    /// Create the day node an instant falls on in a timezone
+    pub fn new_date_node_for_instant(
+        instant: DateTime<Utc>,
+        timezone: &str,
+    ) -> NodeSpaceResult<Self> {
+        let zone = NodeTimezone::parse(timezone)?;
+        Ok(Self::new_date_node_with_timezone(
+            zone.local_date(instant),
+            &zone.to_string(),
+        ))
+    }
+}
+
+/// Build or look up the calendar chain for the local day of an instant
+pub fn ensure_date_path_for_instant(
+    nodes: &[Node],
+    instant: DateTime<Utc>,
+    timezone: &str,
+    layout: CalendarLayout,
+) -> NodeSpaceResult<HierarchyChangeSet> {
+    let date = NodeTimezone::parse(timezone)?.local_date(instant);
+    crate::calendar::ensure_date_path(nodes, date, layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn offset_hours(zone: &str, instant: DateTime<Utc>) -> f64 {
+        let zone = NodeTimezone::parse(zone).unwrap();
+        zone.offset_at(instant).local_minus_utc() as f64 / 3600.0
+    }
+
+    #[test]
+    fn parses_fixed_offsets() {
+        for (input, seconds) in [
+            ("+02:00", 7200),
+            ("-0530", -19800),
+            ("+5", 18000),
+            ("UTC+2", 7200),
+            ("gmt-03:30", -12600),
+            ("+14:00", 50400),
+        ] {
+            let zone = NodeTimezone::parse(input).unwrap();
+            assert!(zone.is_fixed_offset(), "{input}");
+            assert_eq!(
+                zone.offset_at(utc(2025, 1, 1, 0, 0)).local_minus_utc(),
+                seconds
+            );
+        }
+        assert_eq!(NodeTimezone::parse("-0530").unwrap().to_string(), "-05:30");
+    }
+
+    #[test]
+    fn rejects_malformed_offsets_without_panicking() {
+        for input in [
+            "", "+", "02:00", "+123", "+14:30", "+02:60", "+1:2:3", "+a€", "+€€", "-1€", "UTC+é1",
+        ] {
+            assert!(NodeTimezone::parse(input).is_err(), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn resolves_named_zones_and_aliases() {
+        let berlin = NodeTimezone::parse("europe/berlin").unwrap();
+        assert!(!berlin.is_fixed_offset());
+        assert_eq!(berlin.to_string(), "Europe/Berlin");
+        assert_eq!(NodeTimezone::parse("Z").unwrap().to_string(), "UTC");
+        assert_eq!(
+            NodeTimezone::parse("Asia/Calcutta").unwrap().to_string(),
+            "Asia/Kolkata"
+        );
+        assert!(NodeTimezone::parse("Mars/Olympus").is_err());
+        assert!(NodeTimezone::known_zones().any(|z| z == "Australia/Sydney"));
+    }
+
+    #[test]
+    fn united_states_daylight_time() {
+        // 2025: 9 March 02:00 EST to 2 November 02:00 EDT
+        assert_eq!(
+            offset_hours("America/New_York", utc(2025, 3, 9, 6, 59)),
+            -5.0
+        );
+        assert_eq!(
+            offset_hours("America/New_York", utc(2025, 3, 9, 7, 0)),
+            -4.0
+        );
+        assert_eq!(
+            offset_hours("America/New_York", utc(2025, 11, 2, 5, 59)),
+            -4.0
+        );
+        assert_eq!(
+            offset_hours("America/New_York", utc(2025, 11, 2, 6, 0)),
+            -5.0
+        );
+        assert_eq!(
+            offset_hours("America/Los_Angeles", utc(2025, 7, 1, 0, 0)),
+            -7.0
+        );
+        assert_eq!(offset_hours("America/Phoenix", utc(2025, 7, 1, 0, 0)), -7.0);
+    }
+
+    #[test]
+    fn european_union_summer_time() {
+        // 2025: 30 March to 26 October, both at 01:00 UTC
+        assert_eq!(offset_hours("Europe/Berlin", utc(2025, 3, 30, 0, 59)), 1.0);
+        assert_eq!(offset_hours("Europe/Berlin", utc(2025, 3, 30, 1, 0)), 2.0);
+        assert_eq!(offset_hours("Europe/London", utc(2025, 10, 26, 0, 59)), 1.0);
+        assert_eq!(offset_hours("Europe/London", utc(2025, 10, 26, 1, 0)), 0.0);
+        assert_eq!(offset_hours("Europe/Athens", utc(2025, 7, 1, 0, 0)), 3.0);
+    }
+
+    #[test]
+    fn south_east_australia_daylight_time() {
+        // 2025: ends 6 April 03:00 AEDT, starts 5 October 02:00 AEST
+        assert_eq!(
+            offset_hours("Australia/Sydney", utc(2025, 4, 5, 15, 59)),
+            11.0
+        );
+        assert_eq!(
+            offset_hours("Australia/Sydney", utc(2025, 4, 5, 16, 0)),
+            10.0
+        );
+        assert_eq!(
+            offset_hours("Australia/Melbourne", utc(2025, 10, 4, 15, 59)),
+            10.0
+        );
+        assert_eq!(
+            offset_hours("Australia/Melbourne", utc(2025, 10, 4, 16, 0)),
+            11.0
+        );
+        assert_eq!(
+            offset_hours("Australia/Sydney", utc(2025, 1, 1, 0, 0)),
+            11.0
+        );
+    }
+
+    #[test]
+    fn local_date_follows_the_zone() {
+        let instant = utc(2025, 3, 1, 23, 30);
+        assert_eq!(
+            NodeId::for_instant(instant, "Asia/Tokyo").unwrap(),
+            NodeId::for_date(NaiveDate::from_ymd_opt(2025, 3, 2).unwrap())
+        );
+        assert_eq!(
+            NodeId::for_instant(instant, "-05:00").unwrap(),
+            NodeId::for_date(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap())
+        );
+
+        let metadata = DateNodeMetadata::for_instant(instant, "utc+5:30").unwrap();
+        assert_eq!(metadata.date, "2025-03-02");
+        assert_eq!(metadata.timezone, "+05:30");
+        assert!(metadata.parse_timezone().unwrap().is_fixed_offset());
+    }
+}
@@ -0,0 +1,496 @@
+//! In-memory hierarchy assembly for flat node sets
+//!
+//! Storage returns the nodes of a hierarchy as a flat list (typically from a single
+//! `root_id` query). [`NodeTree`] rebuilds the outline from `parent_id`,
+//! `before_sibling` and `next_sibling` so services can traverse it in O(M).
+
+use crate::{Node, NodeId};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Ordered tree assembled from a flat set of nodes
+///
+/// Children are ordered by their `order_key` when every sibling in the group has
+/// one, and by walking the sibling links otherwise. Nodes whose parent is not part
+/// of the set are treated as roots, and nodes that cannot be placed by their links
+/// (broken chains, cycles) are appended after the well-linked ones in input order
+/// so that no node is ever dropped.
+///
+/// Tombstoned nodes are left out, as everywhere the live hierarchy is read. A
+/// live node whose parent is deleted is treated as a root.
+#[derive(Debug, Clone, Default)]
+pub struct NodeTree {
+    nodes: HashMap<NodeId, Node>,
+    roots: Vec<NodeId>,
+    children: HashMap<NodeId, Vec<NodeId>>,
+}
+
+impl NodeTree {
+    /// Build a tree from a flat list of nodes, skipping tombstoned ones
+    pub fn from_nodes(nodes: Vec<Node>) -> Self {
+        let nodes: Vec<Node> = nodes.into_iter().filter(|n| !n.is_deleted()).collect();
+        let order: Vec<NodeId> = nodes.iter().map(|n| n.id.clone()).collect();
+        let nodes: HashMap<NodeId, Node> = nodes.into_iter().map(|n| (n.id.clone(), n)).collect();
+
+        // Group nodes by parent, keeping input order within each group
+        let mut top_level: Vec<NodeId> = Vec::new();
+        let mut groups: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        let mut seen = HashSet::new();
+        for id in &order {
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            match nodes[id].parent_id.as_ref() {
+                Some(parent) if parent != id && nodes.contains_key(parent) => {
+                    groups.entry(parent.clone()).or_default().push(id.clone())
+                }
+                _ => top_level.push(id.clone()),
+            }
+        }
+
+        let mut tree = Self {
+            roots: order_siblings(&top_level, |id| &nodes[id]),
+            children: groups
+                .iter()
+                .map(|(parent, members)| (parent.clone(), order_siblings(members, |id| &nodes[id])))
+                .collect(),
+            nodes,
+        };
+
+        // Nodes caught in a parent cycle are unreachable from any root; promote the
+        // first of each cycle to a root so traversals still cover them.
+        let mut reached: HashSet<NodeId> = tree.pre_order().iter().map(|n| n.id.clone()).collect();
+        seen.clear();
+        for id in order {
+            if !seen.insert(id.clone()) || reached.contains(&id) {
+                continue;
+            }
+            tree.roots.push(id.clone());
+            reached.extend(tree.subtree_ids(&id));
+        }
+
+        tree
+    }
+
+    /// Number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check if the tree contains no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Check if a node is part of the tree
+    pub fn contains(&self, id: &NodeId) -> bool {
+        self.nodes.contains_key(id)
+    }
+
+    /// Look up a node by ID
+    pub fn get(&self, id: &NodeId) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    /// Top-level nodes in sibling order
+    pub fn roots(&self) -> Vec<&Node> {
+        self.resolve(&self.roots)
+    }
+
+    /// IDs of the top-level nodes in sibling order
+    pub fn root_ids(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// Direct children of a node in sibling order
+    pub fn children(&self, id: &NodeId) -> Vec<&Node> {
+        self.resolve(self.child_ids(id))
+    }
+
+    /// IDs of the direct children of a node in sibling order
+    pub fn child_ids(&self, id: &NodeId) -> &[NodeId] {
+        self.children.get(id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Parent of a node, if the parent is part of the tree
+    pub fn parent(&self, id: &NodeId) -> Option<&Node> {
+        self.nodes
+            .get(id)
+            .and_then(|n| n.parent_id.as_ref())
+            .filter(|parent| *parent != id)
+            .and_then(|parent| self.nodes.get(parent))
+    }
+
+    /// All nodes in depth-first pre-order (parent before children)
+    pub fn pre_order(&self) -> Vec<&Node> {
+        let mut visited = HashSet::new();
+        let mut result = Vec::with_capacity(self.nodes.len());
+        for root in &self.roots {
+            self.walk_pre_order(root, &mut visited, &mut result);
+        }
+        result
+    }
+
+    /// All nodes in depth-first post-order (children before parent)
+    pub fn post_order(&self) -> Vec<&Node> {
+        let mut visited = HashSet::new();
+        let mut result = Vec::with_capacity(self.nodes.len());
+        for root in &self.roots {
+            self.walk_post_order(root, &mut visited, &mut result);
+        }
+        result
+    }
+
+    /// All nodes in breadth-first order (level by level)
+    pub fn breadth_first(&self) -> Vec<&Node> {
+        let mut visited = HashSet::new();
+        let mut result = Vec::with_capacity(self.nodes.len());
+        let mut queue: VecDeque<&NodeId> = self.roots.iter().collect();
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get(id) {
+                result.push(node);
+            }
+            queue.extend(self.child_ids(id));
+        }
+        result
+    }
+
+    /// Ancestors of a node, nearest first
+    pub fn ancestors(&self, id: &NodeId) -> Vec<&Node> {
+        let mut visited = HashSet::from([id]);
+        let mut result = Vec::new();
+        let mut current = self.parent(id);
+        while let Some(node) = current {
+            if !visited.insert(&node.id) {
+                break;
+            }
+            result.push(node);
+            current = self.parent(&node.id);
+        }
+        result
+    }
+
+    /// Descendants of a node in pre-order, excluding the node itself
+    pub fn descendants(&self, id: &NodeId) -> Vec<&Node> {
+        let Some((id, _)) = self.nodes.get_key_value(id) else {
+            return Vec::new();
+        };
+        let mut visited = HashSet::from([id]);
+        let mut result = Vec::new();
+        for child in self.child_ids(id) {
+            self.walk_pre_order(child, &mut visited, &mut result);
+        }
+        result
+    }
+
+    /// IDs of a node and all of its descendants in pre-order
+    pub fn subtree_ids(&self, id: &NodeId) -> Vec<NodeId> {
+        let mut visited = HashSet::new();
+        let mut result = Vec::new();
+        self.walk_pre_order(id, &mut visited, &mut result);
+        result.into_iter().map(|n| n.id.clone()).collect()
+    }
+
+    /// Depth of a node (top-level nodes have depth 0)
+    pub fn depth(&self, id: &NodeId) -> Option<usize> {
+        self.nodes.get(id).map(|_| self.ancestors(id).len())
+    }
+
+    /// Consume the tree and return the nodes in pre-order
+    pub fn into_nodes(mut self) -> Vec<Node> {
+        let order: Vec<NodeId> = self.pre_order().iter().map(|n| n.id.clone()).collect();
+        order
+            .into_iter()
+            .filter_map(|id| self.nodes.remove(&id))
+            .collect()
+    }
+
+    fn resolve(&self, ids: &[NodeId]) -> Vec<&Node> {
+        ids.iter().filter_map(|id| self.nodes.get(id)).collect()
+    }
+
+    fn walk_pre_order<'a>(
+        &'a self,
+        start: &'a NodeId,
+        visited: &mut HashSet<&'a NodeId>,
+        result: &mut Vec<&'a Node>,
+    ) {
+        let mut stack = vec![start];
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get(id) {
+                result.push(node);
+            }
+            stack.extend(self.child_ids(id).iter().rev());
+        }
+    }
+
+    fn walk_post_order<'a>(
+        &'a self,
+        start: &'a NodeId,
+        visited: &mut HashSet<&'a NodeId>,
+        result: &mut Vec<&'a Node>,
+    ) {
+        // Each entry is (node, children_expanded)
+        let mut stack = vec![(start, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if expanded {
+                if let Some(node) = self.nodes.get(id) {
+                    result.push(node);
+                }
+                continue;
+            }
+            if !visited.insert(id) {
+                continue;
+            }
+            stack.push((id, true));
+            stack.extend(self.child_ids(id).iter().rev().map(|child| (child, false)));
+        }
+    }
+}
+
+impl From<Vec<Node>> for NodeTree {
+    fn from(nodes: Vec<Node>) -> Self {
+        Self::from_nodes(nodes)
+    }
+}
+
+/// Order a group of siblings
+///
+/// When every member carries an `order_key` the keys decide the order. Otherwise
+/// the sibling links are walked (see [`order_by_links`]).
+pub(crate) fn order_siblings<'a>(
+    members: &'a [NodeId],
+    node: impl Fn(&NodeId) -> &'a Node,
+) -> Vec<NodeId> {
+    if !members.is_empty() && members.iter().all(|id| node(id).order_key.is_some()) {
+        let mut keyed: Vec<(&str, &NodeId)> = members
+            .iter()
+            .map(|id| (node(id).order_key.as_deref().unwrap_or_default(), id))
+            .collect();
+        keyed.sort_by_key(|(key, _)| *key);
+        return keyed.into_iter().map(|(_, id)| id.clone()).collect();
+    }
+    order_by_links(members, node)
+}
+
+/// Order a group of siblings by walking their sibling links
+///
+/// `next_sibling` is authoritative and `before_sibling` fills gaps where a forward
+/// link is missing. Members that cannot be reached from a chain head are appended
+/// in their original order.
+pub(crate) fn order_by_links<'a>(
+    members: &'a [NodeId],
+    node: impl Fn(&NodeId) -> &'a Node,
+) -> Vec<NodeId> {
+    let in_group: HashSet<&NodeId> = members.iter().collect();
+
+    let mut successor: HashMap<&NodeId, &NodeId> = HashMap::new();
+    for id in members {
+        if let Some(next) = node(id).next_sibling.as_ref() {
+            if next != id && in_group.contains(next) {
+                successor.insert(id, next);
+            }
+        }
+    }
+    let mut has_predecessor: HashSet<&NodeId> = successor.values().copied().collect();
+    for id in members {
+        if let Some(before) = node(id).before_sibling.as_ref() {
+            if before != id
+                && in_group.contains(before)
+                && !successor.contains_key(before)
+                && !has_predecessor.contains(id)
+            {
+                successor.insert(before, id);
+                has_predecessor.insert(id);
+            }
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(members.len());
+    let mut visited: HashSet<&NodeId> = HashSet::new();
+    let heads = members.iter().filter(|id| !has_predecessor.contains(id));
+    // Heads first, then anything left over (members of a sibling cycle)
+    for start in heads.chain(members.iter()) {
+        let mut current = Some(start);
+        while let Some(id) = current {
+            if !visited.insert(id) {
+                break;
+            }
+            ordered.push(id.clone());
+            current = successor.get(id).copied();
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, parent: Option<&str>) -> Node {
+        let mut node = Node::with_id(
+            NodeId::from_string(id.to_string()),
+            "text".to_string(),
+            serde_json::json!(id),
+        );
+        node.parent_id = parent.map(|p| NodeId::from_string(p.to_string()));
+        node
+    }
+
+    /// Link `ids` in order as siblings
+    fn link(nodes: &mut [Node], ids: &[&str]) {
+        for pair in ids.windows(2) {
+            let (before, next) = (id(pair[0]), id(pair[1]));
+            for node in nodes.iter_mut() {
+                if node.id == before {
+                    node.next_sibling = Some(next.clone());
+                }
+                if node.id == next {
+                    node.before_sibling = Some(before.clone());
+                }
+            }
+        }
+    }
+
+    fn id(id: &str) -> NodeId {
+        NodeId::from_string(id.to_string())
+    }
+
+    fn ids(nodes: Vec<&Node>) -> Vec<&str> {
+        nodes.into_iter().map(|n| n.id.as_str()).collect()
+    }
+
+    /// root ─┬─ a ─── a1
+    ///       └─ b ─┬─ b1
+    ///             └─ b2
+    fn outline() -> NodeTree {
+        // Children listed out of order; the links decide
+        let mut nodes = vec![
+            node("b2", Some("b")),
+            node("b", Some("root")),
+            node("a1", Some("a")),
+            node("root", None),
+            node("b1", Some("b")),
+            node("a", Some("root")),
+        ];
+        link(&mut nodes, &["a", "b"]);
+        link(&mut nodes, &["b1", "b2"]);
+        NodeTree::from_nodes(nodes)
+    }
+
+    #[test]
+    fn orders_children_by_sibling_links() {
+        let tree = outline();
+        assert_eq!(tree.len(), 6);
+        assert_eq!(tree.root_ids(), &[id("root")]);
+        assert_eq!(ids(tree.children(&id("root"))), vec!["a", "b"]);
+        assert_eq!(ids(tree.children(&id("b"))), vec!["b1", "b2"]);
+        assert!(tree.children(&id("a1")).is_empty());
+        assert_eq!(tree.parent(&id("b1")).map(|n| n.id.as_str()), Some("b"));
+        assert!(tree.parent(&id("root")).is_none());
+    }
+
+    #[test]
+    fn traverses_in_pre_post_and_breadth_first_order() {
+        let tree = outline();
+        assert_eq!(
+            ids(tree.pre_order()),
+            vec!["root", "a", "a1", "b", "b1", "b2"]
+        );
+        assert_eq!(
+            ids(tree.post_order()),
+            vec!["a1", "a", "b1", "b2", "b", "root"]
+        );
+        assert_eq!(
+            ids(tree.breadth_first()),
+            vec!["root", "a", "b", "a1", "b1", "b2"]
+        );
+        let into: Vec<String> = tree.into_nodes().into_iter().map(|n| n.id.0).collect();
+        assert_eq!(into, vec!["root", "a", "a1", "b", "b1", "b2"]);
+    }
+
+    #[test]
+    fn answers_ancestor_descendant_and_depth_queries() {
+        let tree = outline();
+        assert_eq!(ids(tree.ancestors(&id("b2"))), vec!["b", "root"]);
+        assert!(tree.ancestors(&id("root")).is_empty());
+        assert_eq!(ids(tree.descendants(&id("b"))), vec!["b1", "b2"]);
+        assert!(tree.descendants(&id("missing")).is_empty());
+        assert_eq!(tree.subtree_ids(&id("a")), vec![id("a"), id("a1")]);
+        assert_eq!(tree.depth(&id("root")), Some(0));
+        assert_eq!(tree.depth(&id("b2")), Some(2));
+        assert_eq!(tree.depth(&id("missing")), None);
+    }
+
+    #[test]
+    fn order_keys_take_precedence_over_links() {
+        let mut nodes = vec![
+            node("root", None),
+            node("x", Some("root")),
+            node("y", Some("root")),
+        ];
+        link(&mut nodes, &["x", "y"]);
+        nodes[1].order_key = Some("b".to_string());
+        nodes[2].order_key = Some("a".to_string());
+        let tree = NodeTree::from_nodes(nodes.clone());
+        assert_eq!(ids(tree.children(&id("root"))), vec!["y", "x"]);
+
+        // A sibling without a key falls back to the links for the whole group
+        nodes[2].order_key = None;
+        let tree = NodeTree::from_nodes(nodes);
+        assert_eq!(ids(tree.children(&id("root"))), vec!["x", "y"]);
+    }
+
+    #[test]
+    fn keeps_nodes_from_broken_chains_and_cycles() {
+        let mut nodes = vec![
+            node("root", None),
+            node("a", Some("root")),
+            node("b", Some("root")),
+            node("c", Some("root")),
+            node("d", Some("root")),
+        ];
+        // c → d is linked; a and b form a sibling cycle
+        link(&mut nodes, &["c", "d"]);
+        link(&mut nodes, &["a", "b", "a"]);
+        let tree = NodeTree::from_nodes(nodes);
+        assert_eq!(ids(tree.children(&id("root"))), vec!["c", "d", "a", "b"]);
+
+        // Parent cycle between p and q, and a node whose parent is missing
+        let nodes = vec![
+            node("p", Some("q")),
+            node("q", Some("p")),
+            node("orphan", Some("gone")),
+            node("own", Some("own")),
+        ];
+        let tree = NodeTree::from_nodes(nodes);
+        assert_eq!(tree.pre_order().len(), 4);
+        assert!(tree.root_ids().contains(&id("orphan")));
+        assert!(tree.root_ids().contains(&id("own")));
+        assert!(tree.root_ids().contains(&id("p")));
+        assert_eq!(ids(tree.ancestors(&id("p"))), vec!["q"]);
+    }
+
+    #[test]
+    fn leaves_out_tombstoned_nodes() {
+        let mut nodes = vec![
+            node("root", None),
+            node("gone", Some("root")),
+            node("gone-child", Some("gone")),
+            node("kept", Some("root")),
+        ];
+        link(&mut nodes, &["gone", "kept"]);
+        for node in &mut nodes[1..3] {
+            node.deleted_at = Some(chrono::Utc::now());
+        }
+        let tree = NodeTree::from_nodes(nodes);
+        assert_eq!(tree.len(), 2);
+        assert!(!tree.contains(&id("gone")));
+        assert_eq!(ids(tree.children(&id("root"))), vec!["kept"]);
+    }
+}
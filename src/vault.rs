@@ -0,0 +1,907 @@
+//! Obsidian and Logseq vault import
+//!
+//! A vault is a folder of Markdown files. Import maps:
+//!
+//! - each page file to a `document` hierarchy root titled after the file, with
+//!   the file's outline as its children (see [`crate::markdown`]);
+//! - each daily note (`journals/2025_06_30.md`, `2025-06-30.md`) to the day node
+//!   of that date from [`crate::calendar::ensure_date_path`], with the note
+//!   appended after the day's existing children;
+//! - `[[wikilinks]]`, `![[embeds]]`, `((block references))` and Logseq
+//!   `{{embed ...}}` macros to [`RelationshipRef`]s from the block that contains
+//!   them.
+//!
+//! Links resolve by page title, file path or alias, case-insensitively as in
+//! both apps. Links to dates (`[[2025-06-30]]`, `[[Jun 30th, 2025]]`) point to
+//! the day node, and links to pages without a file get an empty document node,
+//! since both apps show those as pages as well.
+//!
+//! Page properties (YAML front matter or leading Logseq `key:: value` lines) and
+//! block properties are moved out of the text into `metadata.vault`. Block IDs,
+//! Logseq `id::` properties and Obsidian `^block-id` markers, are kept there too.
+//! Page properties of daily notes are dropped, as the day node may already exist.
+
+use crate::calendar::{ensure_path, CalendarLayout};
+use crate::hierarchy::{HierarchyChangeSet, Staging};
+use crate::markdown::{atx_heading, parse_markdown};
+use crate::{
+    Node, NodeId, NodeSpaceError, NodeSpaceResult, NodeType, RelationshipRecord, RelationshipRef,
+};
+use chrono::NaiveDate;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Key of the imported properties and block IDs in `Node.metadata`
+const METADATA_KEY: &str = "vault";
+
+/// Relationship type of links and block references
+pub const REFERENCES: &str = "references";
+
+/// Relationship type of embeds
+pub const EMBEDS: &str = "embeds";
+
+/// A Markdown file of a vault
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultFile {
+    /// Path relative to the vault folder, with `/` separators
+    pub path: String,
+    pub markdown: String,
+}
+
+/// A block reference whose target was not found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedReference {
+    pub source_id: NodeId,
+    /// The reference as written, such as `((64a1...))` or `[[Page#^abc]]`
+    pub text: String,
+}
+
+/// Outcome of a vault import
+#[derive(Debug, Clone, Default)]
+pub struct VaultImport {
+    /// Created document, block and date nodes, plus existing nodes relinked
+    pub changes: HierarchyChangeSet,
+    /// One record per distinct link, in document order
+    pub relationships: Vec<RelationshipRecord>,
+    pub unresolved: Vec<UnresolvedReference>,
+}
+
+/// Read the Markdown files of a vault folder, sorted by path
+///
+/// Hidden entries (`.obsidian`, `.trash`, ...) and the top-level `logseq`
+/// folder, which holds Logseq's settings and backups, are skipped.
+pub fn read_vault(dir: &Path) -> NodeSpaceResult<Vec<VaultFile>> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Import the vault in `dir`
+pub fn import_vault(
+    nodes: &[Node],
+    dir: &Path,
+    layout: CalendarLayout,
+) -> NodeSpaceResult<VaultImport> {
+    import_vault_files(nodes, &read_vault(dir)?, layout)
+}
+
+/// Import vault files that were already read
+///
+/// Files are imported in the given order; `layout` shapes the calendar chain
+/// created for daily notes and date links.
+pub fn import_vault_files(
+    nodes: &[Node],
+    files: &[VaultFile],
+    layout: CalendarLayout,
+) -> NodeSpaceResult<VaultImport> {
+    let mut staging = Staging::new(nodes);
+    let mut index = VaultIndex::default();
+    let mut blocks = Vec::new();
+
+    for file in files {
+        let (properties, body) = split_page_properties(&file.markdown);
+        let stem = file_stem(&file.path);
+        let page = match journal_date(stem) {
+            Some(date) => {
+                ensure_path(&mut staging, date, layout)?;
+                NodeId::for_date(date)
+            }
+            None => {
+                let title = properties
+                    .get("title")
+                    .and_then(Value::as_str)
+                    .map_or_else(|| page_title(stem), str::to_string);
+                let mut metadata = Map::new();
+                metadata.insert("path".to_string(), file.path.clone().into());
+                if !properties.is_empty() {
+                    metadata.insert("properties".to_string(), properties.clone().into());
+                }
+                let id = insert_page(&mut staging, &title, Some(metadata))?;
+                index.add_page(&id, &title);
+                index.add_page(&id, &file.path);
+                for alias in aliases(&properties) {
+                    index.add_page(&id, &alias);
+                }
+                id
+            }
+        };
+
+        let parsed = parse_markdown(body, &page, &page);
+        let parents: HashSet<NodeId> = parsed.iter().filter_map(|n| n.parent_id.clone()).collect();
+        for mut node in parsed {
+            let text = node.content.as_str().unwrap_or_default().to_string();
+            let block = split_block(&text);
+            if block.text.is_empty() && block.metadata.is_empty() && !parents.contains(&node.id) {
+                continue;
+            }
+
+            if let Some(Value::String(id)) =
+                block.metadata.get("properties").and_then(|p| p.get("id"))
+            {
+                index
+                    .blocks
+                    .entry(id.to_lowercase())
+                    .or_insert(node.id.clone());
+            }
+            if let Some(Value::String(anchor)) = block.metadata.get("block_id") {
+                index
+                    .anchors
+                    .entry((page.clone(), anchor.to_lowercase()))
+                    .or_insert(node.id.clone());
+            }
+            if let Some((_, heading)) = atx_heading(&block.text) {
+                index
+                    .headings
+                    .entry((page.clone(), heading.to_lowercase()))
+                    .or_insert(node.id.clone());
+            }
+            if !block.is_code {
+                blocks.push((node.id.clone(), page.clone(), block.text.clone()));
+            }
+
+            node.content = block.text.into();
+            if !block.metadata.is_empty() {
+                let mut metadata = Map::new();
+                metadata.insert(METADATA_KEY.to_string(), block.metadata.into());
+                node.metadata = Some(metadata.into());
+            }
+            let parent = node.parent_id.clone().unwrap_or_else(|| page.clone());
+            staging.append_child(node, &parent)?;
+        }
+    }
+
+    let mut import = VaultImport::default();
+    let mut seen = HashSet::new();
+    for (source_id, page, text) in &blocks {
+        for reference in references(text) {
+            let Some((target_id, mut properties)) =
+                index.resolve(&mut staging, &reference, page, layout)?
+            else {
+                import.unresolved.push(UnresolvedReference {
+                    source_id: source_id.clone(),
+                    text: reference.text,
+                });
+                continue;
+            };
+            let relationship_type = if reference.embed { EMBEDS } else { REFERENCES };
+            if !seen.insert((source_id.clone(), target_id.clone(), relationship_type)) {
+                continue;
+            }
+            properties.insert("text".to_string(), reference.text.into());
+            if let Some(alias) = reference.alias {
+                properties.insert("alias".to_string(), alias.into());
+            }
+            import.relationships.push(RelationshipRecord {
+                source_id: source_id.clone(),
+                relationship: RelationshipRef::new(target_id, relationship_type.to_string())
+                    .with_properties(properties.into()),
+            });
+        }
+    }
+
+    import.changes = staging.finish(Vec::new());
+    Ok(import)
+}
+
+/// Titles, paths, headings and block IDs of the imported pages
+#[derive(Debug, Default)]
+struct VaultIndex {
+    /// Page by lowercase title, path (without `.md`) or alias
+    pages: HashMap<String, NodeId>,
+    /// Heading node by page and lowercase heading text
+    headings: HashMap<(NodeId, String), NodeId>,
+    /// Block by Logseq `id::` property
+    blocks: HashMap<String, NodeId>,
+    /// Block by page and Obsidian `^block-id`
+    anchors: HashMap<(NodeId, String), NodeId>,
+}
+
+impl VaultIndex {
+    fn add_page(&mut self, id: &NodeId, name: &str) {
+        self.pages.entry(page_key(name)).or_insert(id.clone());
+    }
+
+    /// Target node and relationship properties of a reference, creating date
+    /// and placeholder pages as needed
+    fn resolve(
+        &mut self,
+        staging: &mut Staging,
+        reference: &Reference,
+        current_page: &NodeId,
+        layout: CalendarLayout,
+    ) -> NodeSpaceResult<Option<(NodeId, Map<String, Value>)>> {
+        let mut properties = Map::new();
+        let (name, fragment) = match &reference.target {
+            Target::Block(id) => {
+                return Ok(self
+                    .blocks
+                    .get(&id.to_lowercase())
+                    .map(|id| (id.clone(), properties)))
+            }
+            Target::Page { name, fragment } => (name.trim(), fragment.as_deref()),
+        };
+
+        let page = if name.is_empty() {
+            current_page.clone()
+        } else if let Some(page) = self.pages.get(&page_key(name)) {
+            page.clone()
+        } else if let Some(date) = journal_date(name) {
+            ensure_path(staging, date, layout)?;
+            NodeId::for_date(date)
+        } else {
+            let id = insert_page(staging, name, None)?;
+            self.add_page(&id, name);
+            id
+        };
+
+        let Some(fragment) = fragment.map(str::trim).filter(|f| !f.is_empty()) else {
+            return Ok(Some((page, properties)));
+        };
+        if let Some(anchor) = fragment.strip_prefix('^') {
+            return Ok(self
+                .anchors
+                .get(&(page, anchor.to_lowercase()))
+                .map(|id| (id.clone(), properties)));
+        }
+        properties.insert("heading".to_string(), fragment.into());
+        let target = self
+            .headings
+            .get(&(page.clone(), fragment.to_lowercase()))
+            .cloned()
+            .unwrap_or(page);
+        Ok(Some((target, properties)))
+    }
+}
+
+/// Stage a new document root
+fn insert_page(
+    staging: &mut Staging,
+    title: &str,
+    metadata: Option<Map<String, Value>>,
+) -> NodeSpaceResult<NodeId> {
+    let mut page = Node::of_kind(NodeType::Document, title.into());
+    if let Some(metadata) = metadata {
+        let mut wrapped = Map::new();
+        wrapped.insert(METADATA_KEY.to_string(), metadata.into());
+        page = page.with_metadata(wrapped.into());
+    }
+    let id = staging.insert(page)?;
+    staging.edit(&id)?.root_id = Some(id.clone());
+    Ok(id)
+}
+
+#[derive(Debug)]
+enum Target {
+    Page {
+        name: String,
+        fragment: Option<String>,
+    },
+    Block(String),
+}
+
+#[derive(Debug)]
+struct Reference {
+    target: Target,
+    alias: Option<String>,
+    embed: bool,
+    text: String,
+}
+
+/// Wikilinks and block references in a block, skipping inline code
+fn references(text: &str) -> Vec<Reference> {
+    let mut found = Vec::new();
+    let mut in_code = false;
+    let mut offset = 0;
+    while let Some(c) = text[offset..].chars().next() {
+        let rest = &text[offset..];
+        if c == '`' {
+            in_code = !in_code;
+        } else if !in_code {
+            let before = &text[..offset];
+            let embed_macro = before.trim_end().ends_with("{{embed");
+            if let Some(inner) = enclosed(rest, "[[", "]]") {
+                let embed = before.ends_with('!') || embed_macro;
+                let (target, alias) = match inner.split_once('|') {
+                    Some((target, alias)) => (target, Some(alias.trim().to_string())),
+                    None => (inner, None),
+                };
+                let (name, fragment) = match target.split_once('#') {
+                    Some((name, fragment)) => (name, Some(fragment.to_string())),
+                    None => (target, None),
+                };
+                let start = if before.ends_with('!') {
+                    offset - 1
+                } else {
+                    offset
+                };
+                found.push(Reference {
+                    target: Target::Page {
+                        name: name.to_string(),
+                        fragment,
+                    },
+                    alias,
+                    embed,
+                    text: text[start..offset + inner.len() + 4].to_string(),
+                });
+                offset += inner.len() + 4;
+                continue;
+            }
+            if let Some(inner) = enclosed(rest, "((", "))")
+                .filter(|inner| uuid::Uuid::parse_str(inner.trim()).is_ok())
+            {
+                found.push(Reference {
+                    target: Target::Block(inner.trim().to_string()),
+                    alias: None,
+                    embed: embed_macro,
+                    text: rest[..inner.len() + 4].to_string(),
+                });
+                offset += inner.len() + 4;
+                continue;
+            }
+        }
+        offset += c.len_utf8();
+    }
+    found
+}
+
+/// Text between `open` at the start of `text` and the next `close` on the
+/// same line
+fn enclosed<'t>(text: &'t str, open: &str, close: &str) -> Option<&'t str> {
+    let rest = text.strip_prefix(open)?;
+    let inner = &rest[..rest.find(close)?];
+    (!inner.is_empty() && !inner.contains('\n') && !inner.contains(open)).then_some(inner)
+}
+
+/// Block text with properties and block ID moved into `metadata`
+#[derive(Debug, Default)]
+struct Block {
+    text: String,
+    metadata: Map<String, Value>,
+    is_code: bool,
+}
+
+fn split_block(text: &str) -> Block {
+    if text.starts_with("```") || text.starts_with("~~~") {
+        return Block {
+            text: text.to_string(),
+            is_code: true,
+            ..Block::default()
+        };
+    }
+
+    let mut properties = Map::new();
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        match property_line(line) {
+            Some((key, value)) => {
+                properties.insert(key.to_string(), value.into());
+            }
+            None => lines.push(line),
+        }
+    }
+
+    let mut metadata = Map::new();
+    if let Some(last) = lines.pop() {
+        match block_anchor(last) {
+            Some((rest, anchor)) => {
+                metadata.insert("block_id".to_string(), anchor.into());
+                if !rest.is_empty() {
+                    lines.push(rest);
+                }
+            }
+            None => lines.push(last),
+        }
+    }
+    if !properties.is_empty() {
+        metadata.insert("properties".to_string(), properties.into());
+    }
+    Block {
+        text: lines.join("\n"),
+        metadata,
+        is_code: false,
+    }
+}
+
+/// Logseq `key:: value` property line
+fn property_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    let (key, value) = match line.split_once(":: ") {
+        Some((key, value)) => (key, value.trim()),
+        None => (line.strip_suffix("::")?, ""),
+    };
+    let valid_key = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid_key.then_some((key, value))
+}
+
+/// Obsidian `^block-id` at the end of a line, returning the rest of the line
+fn block_anchor(line: &str) -> Option<(&str, &str)> {
+    let (rest, anchor) = line.trim_end().rsplit_once('^')?;
+    let valid = !anchor.is_empty()
+        && anchor
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && (rest.is_empty() || rest.ends_with(char::is_whitespace));
+    valid.then(|| (rest.trim_end(), anchor))
+}
+
+/// Page properties and the rest of the document
+///
+/// Reads YAML front matter (`key: value`, inline `[a, b]` lists and `- item`
+/// lists) or leading Logseq `key:: value` lines.
+fn split_page_properties(markdown: &str) -> (Map<String, Value>, &str) {
+    let mut properties = Map::new();
+    let mut lines = markdown.split_inclusive('\n');
+    let mut offset = 0;
+
+    if markdown.lines().next().map(str::trim_end) == Some("---") {
+        offset += lines.next().map_or(0, str::len);
+        let mut list_key: Option<String> = None;
+        for line in lines {
+            offset += line.len();
+            let trimmed = line.trim_end();
+            if trimmed == "---" || trimmed == "..." {
+                return (properties, &markdown[offset..]);
+            }
+            if let (Some(key), Some(item)) = (&list_key, trimmed.trim_start().strip_prefix("- ")) {
+                if let Some(Value::Array(items)) = properties.get_mut(key) {
+                    items.push(unquote(item).into());
+                }
+                continue;
+            }
+            let Some((key, value)) = trimmed.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim().to_string(), value.trim());
+            list_key = None;
+            if value.is_empty() {
+                properties.insert(key.clone(), Value::Array(Vec::new()));
+                list_key = Some(key);
+            } else if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                let items = items
+                    .split(',')
+                    .map(unquote)
+                    .filter(|item| !item.is_empty())
+                    .map(Value::from)
+                    .collect();
+                properties.insert(key, Value::Array(items));
+            } else {
+                properties.insert(key, unquote(value).into());
+            }
+        }
+        // Unterminated front matter is ordinary text
+        return (Map::new(), markdown);
+    }
+
+    for line in lines {
+        match property_line(line) {
+            Some((key, value)) if !line.trim_start().starts_with('-') => {
+                properties.insert(key.to_string(), value.into());
+                offset += line.len();
+            }
+            _ => break,
+        }
+    }
+    (properties, &markdown[offset..])
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+        .unwrap_or(value)
+}
+
+/// `alias` / `aliases` property values, as a list or comma-separated links
+fn aliases(properties: &Map<String, Value>) -> Vec<String> {
+    let values = ["alias", "aliases"]
+        .iter()
+        .filter_map(|key| properties.get(*key));
+    let mut names = Vec::new();
+    for value in values {
+        match value {
+            Value::String(list) => names.extend(list.split(',').map(str::to_string)),
+            Value::Array(items) => {
+                names.extend(items.iter().filter_map(Value::as_str).map(str::to_string))
+            }
+            _ => {}
+        }
+    }
+    names
+        .iter()
+        .map(|name| {
+            let name = name.trim();
+            name.strip_prefix("[[")
+                .and_then(|n| n.strip_suffix("]]"))
+                .unwrap_or(name)
+                .trim()
+                .to_string()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Lookup key of a page title or path
+fn page_key(name: &str) -> String {
+    let name = name.trim();
+    name.strip_suffix(".md").unwrap_or(name).to_lowercase()
+}
+
+fn file_stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+/// Page title of a file name: Logseq writes `/` in namespaced titles as `___`
+/// (or `%2F` in older versions)
+fn page_title(stem: &str) -> String {
+    let stem = stem.replace("___", "/");
+    let bytes = stem.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| stem.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Date of a daily note file name or journal link: `2025_06_30`,
+/// `2025-06-30` or Logseq's default `Jun 30th, 2025`
+fn journal_date(name: &str) -> Option<NaiveDate> {
+    let name = name.trim();
+    if let Some(date) = ["%Y_%m_%d", "%Y-%m-%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(name, format).ok())
+    {
+        return Some(date);
+    }
+    let words: Vec<&str> = name
+        .split_whitespace()
+        .map(|word| {
+            let (core, comma) = match word.strip_suffix(',') {
+                Some(core) => (core, true),
+                None => (word, false),
+            };
+            let digits = core.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            let suffix = &core[digits.len()..];
+            let ordinal = !digits.is_empty()
+                && digits.chars().all(|c| c.is_ascii_digit())
+                && ["st", "nd", "rd", "th"].contains(&suffix.to_lowercase().as_str());
+            match (ordinal, comma) {
+                (true, true) => &word[..digits.len()],
+                (true, false) => digits,
+                (false, _) => word,
+            }
+        })
+        .collect();
+    NaiveDate::parse_from_str(&words.join(" "), "%B %d %Y").ok()
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<VaultFile>) -> NodeSpaceResult<()> {
+    for entry in fs::read_dir(dir).map_err(|e| io_error(dir, e))? {
+        let entry = entry.map_err(|e| io_error(dir, e))?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let file_type = entry.file_type().map_err(|e| io_error(&path, e))?;
+        if name.starts_with('.') {
+            continue;
+        }
+        if file_type.is_dir() {
+            if dir != root || name != "logseq" {
+                collect_files(root, &path, files)?;
+            }
+        } else if file_type.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+        {
+            let markdown = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push(VaultFile {
+                path: relative,
+                markdown,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> NodeSpaceError {
+    NodeSpaceError::IoError {
+        message: format!("{}: {}", path.display(), error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::validate_hierarchy;
+
+    const BLOCK_ID: &str = "64a1b2c3-0000-4000-8000-000000000001";
+
+    fn file(path: &str, markdown: &str) -> VaultFile {
+        VaultFile {
+            path: path.to_string(),
+            markdown: markdown.to_string(),
+        }
+    }
+
+    fn import(nodes: &[Node], files: &[VaultFile]) -> (Vec<Node>, VaultImport) {
+        let import = import_vault_files(nodes, files, CalendarLayout::Monthly).unwrap();
+        let mut nodes = nodes.to_vec();
+        import.changes.apply_to(&mut nodes);
+        (nodes, import)
+    }
+
+    fn by_text<'n>(nodes: &'n [Node], text: &str) -> &'n Node {
+        nodes
+            .iter()
+            .find(|n| n.content.as_str() == Some(text))
+            .unwrap_or_else(|| panic!("no node {:?}", text))
+    }
+
+    /// Target of the relationship written as `text`
+    fn target<'i>(import: &'i VaultImport, text: &str) -> &'i RelationshipRef {
+        &import
+            .relationships
+            .iter()
+            .find(|r| r.relationship.properties["text"] == text)
+            .unwrap_or_else(|| panic!("no relationship {:?}", text))
+            .relationship
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn finds_links_embeds_and_block_references() {
+        let text = format!(
+            "See [[Page#Goals|the goals]], ![[Image.png]], `[[code]]`, \
+             {{{{embed (({id}))}}}}, (({id})) and ((not an id))",
+            id = BLOCK_ID
+        );
+        let found: Vec<String> = references(&text)
+            .into_iter()
+            .map(|r| {
+                let target = match r.target {
+                    Target::Page { name, fragment } => format!("page {} {:?}", name, fragment),
+                    Target::Block(id) => format!("block {}", id),
+                };
+                format!("{} {:?} {} {}", target, r.alias, r.embed, r.text)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "page Page Some(\"Goals\") Some(\"the goals\") false [[Page#Goals|the goals]]"
+                    .to_string(),
+                "page Image.png None None true ![[Image.png]]".to_string(),
+                format!("block {id} None true (({id}))", id = BLOCK_ID),
+                format!("block {id} None false (({id}))", id = BLOCK_ID),
+            ]
+        );
+    }
+
+    #[test]
+    fn enclosed_stays_on_one_line() {
+        assert_eq!(enclosed("[[a]] b", "[[", "]]"), Some("a"));
+        assert_eq!(enclosed("[[]]", "[[", "]]"), None);
+        assert_eq!(enclosed("[[a\nb]]", "[[", "]]"), None);
+        assert_eq!(enclosed("[[a [[b]]", "[[", "]]"), None);
+        assert_eq!(enclosed("[[a", "[[", "]]"), None);
+        assert_eq!(enclosed("x [[a]]", "[[", "]]"), None);
+    }
+
+    #[test]
+    fn splits_front_matter() {
+        let markdown = "---\ntitle: \"Alpha\"\ntags: [one, 'two']\naliases:\n  - A1\n  - \"A 2\"\n---\n# Body\n";
+        let (properties, body) = split_page_properties(markdown);
+        assert_eq!(
+            Value::Object(properties.clone()),
+            serde_json::json!({
+                "title": "Alpha",
+                "tags": ["one", "two"],
+                "aliases": ["A1", "A 2"],
+            })
+        );
+        assert_eq!(body, "# Body\n");
+        assert_eq!(aliases(&properties), vec!["A1", "A 2"]);
+
+        let unterminated = "---\ntitle: Alpha\n";
+        let (properties, body) = split_page_properties(unterminated);
+        assert!(properties.is_empty());
+        assert_eq!(body, unterminated);
+    }
+
+    #[test]
+    fn splits_logseq_page_and_block_properties() {
+        let markdown = "title:: Alpha\nalias:: [[A1]], A2\n\n- first\n";
+        let (properties, body) = split_page_properties(markdown);
+        assert_eq!(properties["title"], "Alpha");
+        assert_eq!(aliases(&properties), vec!["A1", "A2"]);
+        assert_eq!(body, "\n- first\n");
+
+        let block = split_block(&format!("Text\nid:: {}\ncollapsed:: true", BLOCK_ID));
+        assert_eq!(block.text, "Text");
+        assert_eq!(
+            Value::Object(block.metadata),
+            serde_json::json!({ "properties": { "id": BLOCK_ID, "collapsed": "true" } })
+        );
+
+        let block = split_block("Para\nsecond line ^abc-1");
+        assert_eq!(block.text, "Para\nsecond line");
+        assert_eq!(block.metadata["block_id"], "abc-1");
+
+        let block = split_block("2^10 is 1024 and x^id");
+        assert_eq!(block.text, "2^10 is 1024 and x^id");
+        assert!(block.metadata.is_empty());
+
+        let code = "```\nkey:: value\n```";
+        let block = split_block(code);
+        assert!(block.is_code);
+        assert_eq!(block.text, code);
+    }
+
+    #[test]
+    fn parses_journal_names() {
+        for (name, expected) in [
+            ("2025_06_30", date(2025, 6, 30)),
+            ("2025-06-30", date(2025, 6, 30)),
+            ("Jun 30th, 2025", date(2025, 6, 30)),
+            ("June 1st, 2025", date(2025, 6, 1)),
+            ("Jun 2nd 2025", date(2025, 6, 2)),
+            ("aug 23RD, 2024", date(2024, 8, 23)),
+        ] {
+            assert_eq!(journal_date(name), Some(expected), "{}", name);
+        }
+        for name in ["Projects", "2025-13-01", "Jun 31st, 2025", "Jun 30x, 2025"] {
+            assert_eq!(journal_date(name), None, "{}", name);
+        }
+        assert_eq!(page_title("Projects___Alpha%3F"), "Projects/Alpha?");
+        assert_eq!(file_stem("journals/2025_06_30.md"), "2025_06_30");
+    }
+
+    #[test]
+    fn journal_import_attaches_an_existing_day_node() {
+        let day = date(2025, 6, 30);
+        let existing = Node::new_date_node(day);
+        let (nodes, _) = import(
+            std::slice::from_ref(&existing),
+            &[file("journals/2025_06_30.md", "- Morning\n  - Coffee\n")],
+        );
+
+        let year = NodeId::for_year(2025);
+        let month = NodeId::for_month(2025, 6);
+        let day_node = nodes.iter().find(|n| n.id == existing.id).unwrap();
+        assert_eq!(day_node.parent_id.as_ref(), Some(&month));
+        assert_eq!(day_node.root_id.as_ref(), Some(&year));
+
+        let morning = by_text(&nodes, "Morning");
+        let coffee = by_text(&nodes, "Coffee");
+        assert_eq!(morning.parent_id.as_ref(), Some(&existing.id));
+        assert_eq!(coffee.parent_id.as_ref(), Some(&morning.id));
+        assert!([morning, coffee]
+            .iter()
+            .all(|n| n.root_id.as_ref() == Some(&year)));
+        assert_eq!(nodes.iter().filter(|n| n.id == existing.id).count(), 1);
+        assert!(validate_hierarchy(&nodes).is_empty());
+    }
+
+    #[test]
+    fn resolves_titles_paths_aliases_headings_and_blocks() {
+        let alpha = file(
+            "Projects/Alpha.md",
+            &format!(
+                "---\naliases: [A1]\n---\n# Goals\n- Task ^blk\n- Logged\n  id:: {}\n",
+                BLOCK_ID
+            ),
+        );
+        let notes = file(
+            "Notes.md",
+            &format!(
+                "- [[alpha]]\n- [[A1]]\n- [[projects/alpha]]\n- [[Alpha#goals]]\n\
+                 - [[Alpha#^BLK]]\n- (({id}))\n- ![[Alpha]]\n- [[Missing]]\n\
+                 - [[Alpha#^nope]]\n- ((64a1b2c3-0000-4000-8000-0000000000ff))\n\
+                 - [[Jun 30th, 2025]] [[Jun 30th, 2025]]\n",
+                id = BLOCK_ID
+            ),
+        );
+        let (nodes, import) = import(&[], &[alpha, notes]);
+        assert!(validate_hierarchy(&nodes).is_empty());
+
+        let page = nodes
+            .iter()
+            .find(|n| n.content.as_str() == Some("Alpha"))
+            .unwrap();
+        assert_eq!(page.r#type, NodeType::Document.as_str());
+        for text in ["[[alpha]]", "[[A1]]", "[[projects/alpha]]"] {
+            let relationship = target(&import, text);
+            assert_eq!(relationship.target_id, page.id, "{}", text);
+            assert_eq!(relationship.relationship_type, REFERENCES);
+        }
+
+        let heading = target(&import, "[[Alpha#goals]]");
+        assert_eq!(heading.target_id, by_text(&nodes, "# Goals").id);
+        assert_eq!(heading.properties["heading"], "goals");
+        assert_eq!(
+            target(&import, "[[Alpha#^BLK]]").target_id,
+            by_text(&nodes, "Task").id
+        );
+        assert_eq!(
+            target(&import, &format!("(({}))", BLOCK_ID)).target_id,
+            by_text(&nodes, "Logged").id
+        );
+        assert_eq!(target(&import, "![[Alpha]]").relationship_type, EMBEDS);
+
+        let missing = target(&import, "[[Missing]]");
+        let placeholder = nodes.iter().find(|n| n.id == missing.target_id).unwrap();
+        assert_eq!(placeholder.content, "Missing");
+        assert_eq!(placeholder.root_id.as_ref(), Some(&placeholder.id));
+
+        assert_eq!(
+            target(&import, "[[Jun 30th, 2025]]").target_id,
+            NodeId::for_date(date(2025, 6, 30))
+        );
+        let date_links = import
+            .relationships
+            .iter()
+            .filter(|r| r.relationship.properties["text"] == "[[Jun 30th, 2025]]")
+            .count();
+        assert_eq!(date_links, 1);
+
+        let unresolved: Vec<&str> = import.unresolved.iter().map(|u| u.text.as_str()).collect();
+        assert_eq!(
+            unresolved,
+            vec![
+                "[[Alpha#^nope]]",
+                "((64a1b2c3-0000-4000-8000-0000000000ff))"
+            ]
+        );
+    }
+}